@@ -1,6 +1,18 @@
-use crate::{Account, Config, Settings, UIComponents, clear_screen, impl_settings};
+use crate::start::cloudflare_api::{CloudflareAuth, CloudflareClient, RequestBudget};
+use crate::start::dns_operations::provider_for;
+use crate::{Account, Config, ProviderKind, Settings, UIComponents, clear_screen, impl_settings};
 use anyhow::Result;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// 可选的 DNS 服务商，顺序即菜单顺序
+const PROVIDERS: [(ProviderKind, &str); 5] = [
+    (ProviderKind::Cloudflare, "Cloudflare"),
+    (ProviderKind::DnsPod, "DNSPod"),
+    (ProviderKind::AliDns, "阿里云DNS"),
+    (ProviderKind::Rfc2136, "RFC 2136（自建DNS服务器）"),
+    (ProviderKind::Simulate, "离线模拟"),
+];
 
 // 独立函数，用于获取账户输入
 pub fn get_account_input(
@@ -62,29 +74,280 @@ pub fn get_account_input(
         name
     };
 
-    let x_email = ui.get_email_input(
-        "请输入账户登陆邮箱",
-        default_values.map(|d| d.x_email.as_str()).unwrap_or(""),
+    // DNS 服务商
+    let provider_items: Vec<&str> = PROVIDERS.iter().map(|(_, name)| *name).collect();
+    let default_provider = default_values
+        .and_then(|d| PROVIDERS.iter().position(|(kind, _)| *kind == d.provider))
+        .unwrap_or(0);
+    let provider = match ui.show_menu("请选择DNS服务商", &provider_items, default_provider)? {
+        Some(index) => PROVIDERS[index].0,
+        None => return Ok(None),
+    };
+
+    match provider {
+        ProviderKind::Cloudflare => {}
+        ProviderKind::Rfc2136 => return get_rfc2136_account(ui, account_name, default_values),
+        ProviderKind::Simulate => return get_simulate_account(ui, account_name, default_values),
+        _ => return get_access_key_account(ui, account_name, provider, default_values),
+    }
+
+    // 认证方式：API Token 或 Global API Key
+    let auth_items = ["API Token（推荐）", "Global API Key"];
+    let default_auth = match default_values {
+        Some(d) if d.api_token.is_none() => 1,
+        _ => 0,
+    };
+    let use_token = match ui.show_menu("请选择认证方式", &auth_items, default_auth)? {
+        Some(value) => value == 0,
+        None => return Ok(None),
+    };
+
+    let (x_email, api_key, api_token) = if use_token {
+        let api_token = ui.get_non_empty_input_with_default(
+            "请输入API Token",
+            default_values
+                .and_then(|d| d.api_token.as_deref())
+                .unwrap_or(""),
+        )?;
+        (String::new(), String::new(), Some(api_token))
+    } else {
+        let x_email = ui.get_email_input(
+            "请输入账户登陆邮箱",
+            default_values.map(|d| d.x_email.as_str()).unwrap_or(""),
+        )?;
+
+        let api_key = ui.get_non_empty_input_with_default(
+            "请输入API Key",
+            default_values.map(|d| d.api_key.as_str()).unwrap_or(""),
+        )?;
+        (x_email, api_key, None)
+    };
+
+    let zone_id = ui
+        .get_text_input(
+            "请输入区域ID（输入0则根据域名自动识别）",
+            default_values
+                .map(|d| d.zone_id.as_str())
+                .filter(|z| !z.is_empty())
+                .unwrap_or("0"),
+            |input| !input.trim().is_empty(),
+        )?
+        .trim()
+        .to_string();
+    let zone_id = if zone_id == "0" {
+        String::new()
+    } else {
+        zone_id
+    };
+
+    Ok(Some(Account {
+        account_name,
+        provider,
+        x_email,
+        zone_id,
+        api_key,
+        api_token,
+        access_key_id: String::new(),
+        access_key_secret: String::new(),
+        server: String::new(),
+        tsig_key_name: String::new(),
+        tsig_algorithm: String::new(),
+        tsig_secret: String::new(),
+        records_file: String::new(),
+    }))
+}
+
+/// 获取 DNSPod / 阿里云DNS 账户的密钥输入
+fn get_access_key_account(
+    ui: &UIComponents,
+    account_name: String,
+    provider: ProviderKind,
+    default_values: Option<&Account>,
+) -> Result<Option<Account>> {
+    let (id_prompt, secret_prompt) = match provider {
+        ProviderKind::DnsPod => ("请输入SecretId", "请输入SecretKey"),
+        _ => ("请输入AccessKey ID", "请输入AccessKey Secret"),
+    };
+    // 切换服务商时不沿用原有密钥
+    let defaults = default_values.filter(|d| d.provider == provider);
+
+    let access_key_id = ui.get_non_empty_input_with_default(
+        id_prompt,
+        defaults.map(|d| d.access_key_id.as_str()).unwrap_or(""),
     )?;
+    let access_key_secret = ui.get_non_empty_input_with_default(
+        secret_prompt,
+        defaults.map(|d| d.access_key_secret.as_str()).unwrap_or(""),
+    )?;
+
+    let zone_id = ui
+        .get_text_input(
+            "请输入主域名（输入0则根据域名自动识别）",
+            defaults
+                .map(|d| d.zone_id.as_str())
+                .filter(|z| !z.is_empty())
+                .unwrap_or("0"),
+            |input| !input.trim().is_empty(),
+        )?
+        .trim()
+        .to_string();
+    let zone_id = if zone_id == "0" {
+        String::new()
+    } else {
+        zone_id
+    };
+
+    Ok(Some(Account {
+        account_name,
+        provider,
+        x_email: String::new(),
+        zone_id,
+        api_key: String::new(),
+        api_token: None,
+        access_key_id,
+        access_key_secret,
+        server: String::new(),
+        tsig_key_name: String::new(),
+        tsig_algorithm: String::new(),
+        tsig_secret: String::new(),
+        records_file: String::new(),
+    }))
+}
 
+/// 获取 RFC 2136 账户的服务器与 TSIG 密钥输入
+fn get_rfc2136_account(
+    ui: &UIComponents,
+    account_name: String,
+    default_values: Option<&Account>,
+) -> Result<Option<Account>> {
+    let defaults = default_values.filter(|d| d.provider == ProviderKind::Rfc2136);
+
+    let server = ui.get_non_empty_input_with_default(
+        "请输入DNS服务器地址（如 192.0.2.1 或 ns1.example.com:53）",
+        defaults.map(|d| d.server.as_str()).unwrap_or(""),
+    )?;
     let zone_id = ui.get_non_empty_input_with_default(
-        "请输入区域ID",
-        default_values.map(|d| d.zone_id.as_str()).unwrap_or(""),
+        "请输入区域名（如 example.com）",
+        defaults.map(|d| d.zone_id.as_str()).unwrap_or(""),
+    )?;
+    let tsig_key_name = ui.get_non_empty_input_with_default(
+        "请输入TSIG密钥名",
+        defaults.map(|d| d.tsig_key_name.as_str()).unwrap_or(""),
     )?;
 
-    let api_key = ui.get_non_empty_input_with_default(
-        "请输入API Key",
-        default_values.map(|d| d.api_key.as_str()).unwrap_or(""),
+    let algorithm_items = ["hmac-sha256", "hmac-sha512", "hmac-sha1"];
+    let default_algorithm = defaults
+        .and_then(|d| algorithm_items.iter().position(|a| *a == d.tsig_algorithm))
+        .unwrap_or(0);
+    let tsig_algorithm =
+        match ui.show_menu("请选择TSIG算法", &algorithm_items, default_algorithm)? {
+            Some(value) => algorithm_items[value].to_string(),
+            None => return Ok(None),
+        };
+
+    let tsig_secret = ui.get_non_empty_input_with_default(
+        "请输入TSIG密钥（Base64）",
+        defaults.map(|d| d.tsig_secret.as_str()).unwrap_or(""),
     )?;
 
     Ok(Some(Account {
         account_name,
-        x_email,
+        provider: ProviderKind::Rfc2136,
+        x_email: String::new(),
         zone_id,
-        api_key,
+        api_key: String::new(),
+        api_token: None,
+        access_key_id: String::new(),
+        access_key_secret: String::new(),
+        server,
+        tsig_key_name,
+        tsig_algorithm,
+        tsig_secret,
+        records_file: String::new(),
     }))
 }
 
+/// 获取离线模拟账户的记录文件与区域输入
+fn get_simulate_account(
+    ui: &UIComponents,
+    account_name: String,
+    default_values: Option<&Account>,
+) -> Result<Option<Account>> {
+    let defaults = default_values.filter(|d| d.provider == ProviderKind::Simulate);
+
+    let records_file = ui.get_non_empty_input_with_default(
+        "请输入模拟记录文件路径",
+        defaults
+            .map(|d| d.records_file.as_str())
+            .unwrap_or("cfrs-simulate.json"),
+    )?;
+    let zone_id = ui.get_non_empty_input_with_default(
+        "请输入模拟的区域名（如 example.com）",
+        defaults.map(|d| d.zone_id.as_str()).unwrap_or(""),
+    )?;
+
+    Ok(Some(Account {
+        account_name,
+        provider: ProviderKind::Simulate,
+        x_email: String::new(),
+        zone_id,
+        api_key: String::new(),
+        api_token: None,
+        access_key_id: String::new(),
+        access_key_secret: String::new(),
+        server: String::new(),
+        tsig_key_name: String::new(),
+        tsig_algorithm: String::new(),
+        tsig_secret: String::new(),
+        records_file,
+    }))
+}
+
+/// 账户的区域ID描述，未指定时自动识别
+fn describe_zone(account: &Account) -> &str {
+    if account.zone_id.is_empty() {
+        "自动识别"
+    } else {
+        &account.zone_id
+    }
+}
+
+/// 账户的认证信息描述，用于列表展示
+fn describe_auth(account: &Account) -> String {
+    match account.provider {
+        ProviderKind::DnsPod => {
+            return format!(
+                "服务商: DNSPod\n  SecretId: {}\n  SecretKey: {}",
+                account.access_key_id, account.access_key_secret
+            );
+        }
+        ProviderKind::AliDns => {
+            return format!(
+                "服务商: 阿里云DNS\n  AccessKey ID: {}\n  AccessKey Secret: {}",
+                account.access_key_id, account.access_key_secret
+            );
+        }
+        ProviderKind::Rfc2136 => {
+            return format!(
+                "服务商: RFC 2136\n  服务器: {}\n  TSIG密钥名: {}\n  TSIG算法: {}\n  TSIG密钥: {}",
+                account.server, account.tsig_key_name, account.tsig_algorithm, account.tsig_secret
+            );
+        }
+        ProviderKind::Simulate => {
+            return format!("服务商: 离线模拟\n  记录文件: {}", account.records_file);
+        }
+        ProviderKind::Cloudflare => {}
+    }
+
+    match &account.api_token {
+        Some(token) => format!("认证方式: API Token\n  API Token: {}", token),
+        None => format!(
+            "认证方式: Global API Key\n  邮箱: {}\n  API Key: {}",
+            account.x_email, account.api_key
+        ),
+    }
+}
+
 pub struct AccountSettings {
     config_path: PathBuf,
     config: Config,
@@ -134,8 +397,10 @@ impl AccountSettings {
                 .iter()
                 .map(|acc| {
                     format!(
-                        "- 账户组: {}\n  邮箱: {}\n  区域ID: {}\n  API Key: {}",
-                        acc.account_name, acc.x_email, acc.zone_id, acc.api_key
+                        "- 账户组: {}\n  区域ID: {}\n  {}",
+                        acc.account_name,
+                        describe_zone(acc),
+                        describe_auth(acc)
                     )
                 })
                 .collect::<Vec<String>>();
@@ -150,6 +415,11 @@ impl AccountSettings {
             None => return Ok(()),
         };
 
+        if !self.verify_account(&account)? {
+            clear_screen()?;
+            return Ok(());
+        }
+
         self.config.account.push(account);
 
         self.config.save(self.config_path.as_path())?;
@@ -233,18 +503,12 @@ impl AccountSettings {
             "账户组: {}",
             self.config.account[selection_index].account_name
         ))?;
-        self.ui.show_message(&format!(
-            "邮箱: {}",
-            self.config.account[selection_index].x_email
-        ))?;
         self.ui.show_message(&format!(
             "区域ID: {}",
-            self.config.account[selection_index].zone_id
-        ))?;
-        self.ui.show_message(&format!(
-            "API Key: {}",
-            self.config.account[selection_index].api_key
+            describe_zone(&self.config.account[selection_index])
         ))?;
+        self.ui
+            .show_message(&describe_auth(&self.config.account[selection_index]))?;
         self.ui.show_message("")?;
         clear_screen()?;
 
@@ -257,6 +521,11 @@ impl AccountSettings {
             None => return Ok(()),
         };
 
+        if !self.verify_account(&account)? {
+            clear_screen()?;
+            return Ok(());
+        }
+
         // 保存新的账户组名称
         let new_account_name = account.account_name;
 
@@ -265,6 +534,15 @@ impl AccountSettings {
         account_ref.x_email = account.x_email;
         account_ref.zone_id = account.zone_id;
         account_ref.api_key = account.api_key;
+        account_ref.api_token = account.api_token;
+        account_ref.provider = account.provider;
+        account_ref.access_key_id = account.access_key_id;
+        account_ref.access_key_secret = account.access_key_secret;
+        account_ref.server = account.server;
+        account_ref.tsig_key_name = account.tsig_key_name;
+        account_ref.tsig_algorithm = account.tsig_algorithm;
+        account_ref.tsig_secret = account.tsig_secret;
+        account_ref.records_file = account.records_file;
 
         // 如果账户组名称已更改，则更新所有相关的解析组
         if new_account_name != current_account_name
@@ -274,6 +552,11 @@ impl AccountSettings {
                 if resolve.add_ddns == current_account_name {
                     resolve.add_ddns = new_account_name.clone();
                 }
+                for target in &mut resolve.targets {
+                    if target.add_ddns == current_account_name {
+                        target.add_ddns = new_account_name.clone();
+                    }
+                }
             }
         }
 
@@ -282,6 +565,50 @@ impl AccountSettings {
         clear_screen()?;
         Ok(())
     }
+
+    /// 保存前校验 API Token 或服务商密钥，返回是否继续保存
+    fn verify_account(&self, account: &Account) -> Result<bool> {
+        if !account.provider.is_cloudflare() {
+            return self.verify_access_key(account);
+        }
+        if account.api_token.is_none() {
+            return Ok(true);
+        }
+
+        self.ui.show_message("正在验证 API Token...")?;
+        let client = CloudflareClient::new(&CloudflareAuth::from(account), Duration::from_secs(10));
+
+        match client.verify_token() {
+            Ok(()) => {
+                self.ui.show_message("API Token 验证成功")?;
+                Ok(true)
+            }
+            Err(e) => {
+                self.ui.show_message(&format!("错误: {}", e))?;
+                self.ui
+                    .confirm("API Token 验证未通过，是否仍要保存？", false)
+            }
+        }
+    }
+
+    /// 通过列出域名校验非 Cloudflare 服务商的配置
+    fn verify_access_key(&self, account: &Account) -> Result<bool> {
+        self.ui.show_message("正在验证密钥...")?;
+        let budget = RequestBudget::from_env();
+        let provider = provider_for(account, &budget);
+
+        match provider.list_zones() {
+            Ok(zones) => {
+                self.ui
+                    .show_message(&format!("密钥验证成功，共有 {} 个域名", zones.len()))?;
+                Ok(true)
+            }
+            Err(e) => {
+                self.ui.show_message(&format!("错误: {}", e))?;
+                self.ui.confirm("密钥验证未通过，是否仍要保存？", false)
+            }
+        }
+    }
 }
 
 impl_settings!(AccountSettings);
\ No newline at end of file
@@ -0,0 +1,199 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::env;
+use std::time::Duration;
+
+/// 可通过环境变量覆盖的后端地址，便于把请求指向本地替身服务
+pub struct Endpoint {
+    pub env_key: &'static str,
+    pub default: &'static str,
+}
+
+impl Endpoint {
+    /// 获取实际使用的地址（去掉末尾的斜杠）
+    pub fn base_url(&self) -> String {
+        env::var(self.env_key)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| self.default.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+}
+
+/// Cloudflare API 地址
+pub const CLOUDFLARE_API: Endpoint = Endpoint {
+    env_key: "CFRS_CLOUDFLARE_API",
+    default: "https://api.cloudflare.com/client/v4",
+};
+
+/// DNSPod（腾讯云 API 3.0）地址
+pub const DNSPOD_API: Endpoint = Endpoint {
+    env_key: "CFRS_DNSPOD_API",
+    default: "https://dnspod.tencentcloudapi.com",
+};
+
+/// 阿里云 DNS 地址
+pub const ALIDNS_API: Endpoint = Endpoint {
+    env_key: "CFRS_ALIDNS_API",
+    default: "https://alidns.aliyuncs.com",
+};
+
+/// 去掉协议与路径后的主机名（含端口）
+pub fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    rest.split('/').next().unwrap_or(rest)
+}
+
+/// 请求体
+pub enum Body<'a> {
+    Json(&'a Value),
+    Form(&'a str),
+}
+
+/// HTTP 响应
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// 状态码是否为 2xx
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// 按名称获取响应头（不区分大小写）
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 将响应体解析为 JSON
+    pub fn json(&self) -> Result<Value> {
+        serde_json::from_str(&self.body)
+            .map_err(|e| anyhow::anyhow!("解析响应JSON失败 (HTTP {}): {}", self.status, e))
+    }
+}
+
+/// 网络请求失败
+#[derive(Debug)]
+pub struct RequestError {
+    message: String,
+    /// 请求是否可能已到达服务端，域名解析或建立连接失败时为 false
+    pub sent: bool,
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+/// 失败发生在请求发出之前，服务端一定没有收到
+fn is_unsent(error: &ureq::Error) -> bool {
+    use ureq::Timeout;
+    matches!(
+        error,
+        ureq::Error::HostNotFound
+            | ureq::Error::ConnectionFailed
+            | ureq::Error::Timeout(Timeout::Resolve | Timeout::Connect)
+    )
+}
+
+/// 进程内 HTTP 客户端，所有网络请求统一经由此处发出
+pub struct HttpClient {
+    agent: ureq::Agent,
+    base_url: String,
+}
+
+impl HttpClient {
+    /// 创建客户端，`base_url` 为空时请求需传入完整 URL
+    pub fn new(base_url: &str, timeout: Duration) -> Self {
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(timeout))
+            .http_status_as_error(false)
+            .build()
+            .into();
+
+        Self {
+            agent,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// 拼接完整 URL，已是完整地址的直接返回
+    pub fn url(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") || self.base_url.is_empty() {
+            path.to_string()
+        } else if path.starts_with('/') {
+            format!("{}{}", self.base_url, path)
+        } else {
+            format!("{}/{}", self.base_url, path)
+        }
+    }
+
+    /// 发送 GET 请求
+    pub fn get(&self, path: &str, headers: &[(&str, &str)]) -> Result<HttpResponse> {
+        self.send("GET", path, headers, None)
+    }
+
+    /// 发送任意方法的请求
+    pub fn send(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: Option<Body>,
+    ) -> Result<HttpResponse> {
+        let url = self.url(path);
+
+        let mut builder = ureq::http::Request::builder().method(method).uri(&url);
+        for (key, value) in headers {
+            builder = builder.header(*key, *value);
+        }
+
+        let payload = match body {
+            Some(Body::Json(value)) => {
+                builder = builder.header("Content-Type", "application/json");
+                value.to_string().into_bytes()
+            }
+            Some(Body::Form(form)) => {
+                builder = builder.header("Content-Type", "application/x-www-form-urlencoded");
+                form.as_bytes().to_vec()
+            }
+            None => Vec::new(),
+        };
+
+        let request = builder.body(payload)?;
+        let mut response = self.agent.run(request).map_err(|e| RequestError {
+            message: format!("请求 {} 失败: {}", url, e),
+            sent: !is_unsent(&e),
+        })?;
+
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response
+            .body_mut()
+            .read_to_string()
+            .map_err(|e| RequestError {
+                message: format!("读取 {} 响应失败: {}", url, e),
+                sent: true,
+            })?;
+
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
\ No newline at end of file
@@ -29,6 +29,9 @@ pub fn print_section_header(title: &str) {
 // 全局常量
 pub const CONFIG_FILE: &str = "cf.yaml";
 
+// 没有地址来源时默认从内置 Cloudflare IP 段抽取的IP数量
+pub const DEFAULT_BUILTIN_SAMPLE: u32 = 1000;
+
 // CloudflareST-Rust 全局常量，根据平台不同设置不同的值
 #[cfg(target_os = "windows")]
 pub const CLOUDFLAREST_RUST: &str = "CloudflareST-Rust.exe";
@@ -36,6 +39,13 @@ pub const CLOUDFLAREST_RUST: &str = "CloudflareST-Rust.exe";
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 pub const CLOUDFLAREST_RUST: &str = "CloudflareST-Rust";
 
+// XIU2 CloudflareSpeedTest（Go 版）的可执行文件名
+#[cfg(target_os = "windows")]
+pub const CLOUDFLARE_SPEED_TEST: &str = "cfst.exe";
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub const CLOUDFLARE_SPEED_TEST: &str = "cfst";
+
 // -- 账户管理 --
 mod account_settings;
 use account_settings::AccountSettings;
@@ -60,6 +70,9 @@ use start::Start;
 // -- 推送 --
 mod push;
 
+// -- HTTP 客户端 --
+mod http_client;
+
 // -- 通用设置trait --
 mod settings_trait;
 use settings_trait::Settings;
@@ -68,12 +81,54 @@ use settings_trait::Settings;
 mod ui_components;
 pub use ui_components::UIComponents;
 
+/// DNS 服务商
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[default]
+    Cloudflare,
+    DnsPod,
+    AliDns,
+    Rfc2136,
+    Simulate,
+}
+
+impl ProviderKind {
+    pub fn is_cloudflare(&self) -> bool {
+        *self == ProviderKind::Cloudflare
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Account {
     pub account_name: String,
+    #[serde(default, skip_serializing_if = "ProviderKind::is_cloudflare")]
+    pub provider: ProviderKind,
+    #[serde(default)]
     pub x_email: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub zone_id: String,
+    #[serde(default)]
     pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<String>,
+    // DNSPod 的 SecretId / SecretKey，或阿里云的 AccessKey ID / Secret
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub access_key_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub access_key_secret: String,
+    // RFC 2136 动态更新的服务器地址与 TSIG 密钥
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub server: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub tsig_key_name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub tsig_algorithm: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub tsig_secret: String,
+    // 离线模拟服务商保存记录的 JSON 文件
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub records_file: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -88,6 +143,184 @@ pub struct Resolve {
     pub v4_url: String,
     pub v6_url: String,
     pub push_mod: String,
+    #[serde(default)]
+    pub proxied: bool,
+    #[serde(default = "default_ttl")]
+    pub ttl: u32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub comment: String,
+    #[serde(default = "default_min_records")]
+    pub min_records: u32,
+    // 把域名下没有 [CFRS] 标记的已有记录也当作 CFRS 记录处理，更新时加上标记
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub adopt_records: bool,
+    #[serde(default, skip_serializing_if = "Distribution::is_round_robin")]
+    pub distribution: Distribution,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<Target>,
+    // 使用的共享测速配置，设置后忽略本解析组的 cf_command 和 URL
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub test_profile: String,
+    // URL 中网段每 /24（IPv6 为 /48）抽取的IP数量，0 表示原样交给测速程序
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub cidr_sample: u32,
+    // v4_url、v6_url 之外的其他地址来源，合并去重后写入 -f 文件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub v4_sources: Vec<IpSource>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub v6_sources: Vec<IpSource>,
+    // 没有地址来源时，从内置 Cloudflare IP 段按 /24（IPv6 为 /48）随机抽取的IP数量，0 为不使用
+    #[serde(
+        default = "default_builtin_sample",
+        skip_serializing_if = "is_default_builtin_sample"
+    )]
+    pub builtin_sample: u32,
+}
+
+/// 可被多个解析组共用的测速配置，一次运行中只测速一次
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TestProfile {
+    pub profile_name: String,
+    pub cf_command: String,
+    #[serde(default)]
+    pub v4_url: String,
+    #[serde(default)]
+    pub v6_url: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub cidr_sample: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub v4_sources: Vec<IpSource>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub v6_sources: Vec<IpSource>,
+    #[serde(
+        default = "default_builtin_sample",
+        skip_serializing_if = "is_default_builtin_sample"
+    )]
+    pub builtin_sample: u32,
+}
+
+/// 一个IP地址来源，url、file、cidrs 只能设置其中一项
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IpSource {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+    // 本地文件路径
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub file: String,
+    // 直接填写的IP或网段
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cidrs: Vec<String>,
+    // 合并时按权重比例分配数量
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    // 该来源最多取的数量，0 为不限
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub limit: u32,
+}
+
+impl IpSource {
+    pub fn from_url(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            file: String::new(),
+            cidrs: Vec::new(),
+            weight: default_weight(),
+            limit: 0,
+        }
+    }
+}
+
+impl std::fmt::Display for IpSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.url.is_empty() {
+            write!(f, "{}", self.url)
+        } else if !self.file.is_empty() {
+            write!(f, "文件 {}", self.file)
+        } else {
+            write!(f, "网段列表（{} 项）", self.cidrs.len())
+        }
+    }
+}
+
+/// 解析组的附加解析目标，与主目标共用同一次测速结果
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Target {
+    pub add_ddns: String,
+    pub hostname1: String,
+    pub hostname2: String,
+}
+
+/// 测速得到的IP分配到各域名的方式
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case", tag = "mode")]
+pub enum Distribution {
+    /// 按顺序轮流分配给各域名
+    #[default]
+    RoundRobin,
+    /// 每个域名都解析到全部IP
+    All,
+    /// 按顺序切成连续的几段，每个域名一段
+    Chunks,
+    /// 每个域名都解析到最快的若干个IP
+    Best { count: u32 },
+    /// 按顺序为每个域名分配指定数量的IP
+    Counts { counts: Vec<u32> },
+}
+
+impl Distribution {
+    pub fn is_round_robin(&self) -> bool {
+        *self == Distribution::RoundRobin
+    }
+}
+
+impl std::fmt::Display for Distribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Distribution::RoundRobin => write!(f, "轮流分配"),
+            Distribution::All => write!(f, "全部IP"),
+            Distribution::Chunks => write!(f, "连续分段"),
+            Distribution::Best { count } => write!(f, "最快的 {} 个IP", count),
+            Distribution::Counts { counts } => write!(
+                f,
+                "指定数量（{}）",
+                counts
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        }
+    }
+}
+
+// TTL 为 1 表示由 Cloudflare 自动设置
+fn default_ttl() -> u32 {
+    1
+}
+
+// 默认至少要写入 1 条新记录，否则回滚
+fn default_min_records() -> u32 {
+    1
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_builtin_sample() -> u32 {
+    DEFAULT_BUILTIN_SAMPLE
+}
+
+fn is_default_builtin_sample(value: &u32) -> bool {
+    *value == DEFAULT_BUILTIN_SAMPLE
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -136,6 +369,96 @@ pub struct Config {
     pub plugin: Option<Plugin>,
     pub push: Option<Vec<PushConfig>>,
     pub github_push: Option<Vec<GithubPushConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_profile: Option<Vec<TestProfile>>,
+    // 替换内置的 Cloudflare IP 段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloudflare_ranges: Option<CloudflareRanges>,
+    // 使用的测速程序，省略时为 CloudflareST-Rust
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tester: Option<TesterConfig>,
+}
+
+/// 测速程序类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TesterKind {
+    #[default]
+    CloudflareStRust,
+    /// XIU2 的 Go 版 CloudflareSpeedTest
+    CloudflareSpeedTest,
+    /// 其他测速程序，需要填写路径，参数名和结果列名可按需覆盖
+    Custom,
+}
+
+/// 测速程序设置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TesterConfig {
+    #[serde(default)]
+    pub kind: TesterKind,
+    // 可执行文件路径，留空时使用当前目录下该测速程序的默认文件名
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub path: String,
+    #[serde(default, skip_serializing_if = "TesterFlags::is_empty")]
+    pub flags: TesterFlags,
+    #[serde(default, skip_serializing_if = "ResultColumns::is_empty")]
+    pub columns: ResultColumns,
+}
+
+/// 覆盖测速程序默认的参数名，填空字符串表示该测速程序不支持此参数
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TesterFlags {
+    // 下载测速数量
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_count: Option<String>,
+    // 输出结果数量
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub print_count: Option<String>,
+    // 读取IP地址的文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_file: Option<String>,
+    // 写入结果的文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_file: Option<String>,
+    // 未指定结果文件时的默认文件名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_output: Option<String>,
+}
+
+impl TesterFlags {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 测速结果中各列的标题，留空时按常见的列名识别
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ResultColumns {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub ip: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub loss_rate: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub latency: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub speed: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub colo: String,
+}
+
+impl ResultColumns {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 自定义的 Cloudflare IP 段，某一类型留空时仍使用内置的网段
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CloudflareRanges {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub v4: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub v6: Vec<String>,
 }
 
 impl Config {
@@ -156,12 +479,21 @@ impl Config {
 }
 
 fn main() -> Result<()> {
-    let mut args = env::args();
+    // 处理命令行参数：CFRS [--plan] <解析组> [解析组...]
+    let args: Vec<String> = env::args().skip(1).collect();
+    let plan = args.iter().any(|a| a == "--plan");
+    let ddns_names: Vec<String> = args.into_iter().filter(|a| a != "--plan").collect();
+
+    // 预览模式只用于直接执行指定解析组，避免进入交互菜单后误改DNS
+    if plan && ddns_names.is_empty() {
+        return Err(anyhow::anyhow!(
+            "--plan 需要指定解析组，用法: CFRS --plan <解析组> [解析组...]"
+        ));
+    }
 
-    // 处理命令行参数
-    if let Some(ddns_name) = args.nth(1) {
+    if !ddns_names.is_empty() {
         let mut start = Start::new(Path::new(CONFIG_FILE))?;
-        start.run(Some(ddns_name))?;
+        start.run(ddns_names, plan)?;
         return Ok(());
     }
 
@@ -224,7 +556,7 @@ fn push_settings() -> Result<()> {
 
 fn execute_resolve() -> Result<()> {
     let mut start = Start::new(Path::new(CONFIG_FILE))?;
-    start.run(None)?;
+    start.run(Vec::new(), false)?;
     Ok(())
 }
 
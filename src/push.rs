@@ -1,3 +1,6 @@
+use crate::http_client::{Body, Endpoint, HttpClient};
+use crate::start::dns_operations::display_name;
+use crate::start::test_result::{filter_results, parse_results};
 use crate::{
     Config, GithubPushConfig, Settings, error_println, impl_settings, info_println,
     print_section_header, success_println, warning_println,
@@ -9,7 +12,33 @@ use serde_json::Value;
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::time::Duration;
+
+// 各推送后端的接口地址
+const TELEGRAM_API: Endpoint = Endpoint {
+    env_key: "CFRS_TELEGRAM_API",
+    default: "https://api.telegram.org",
+};
+const PUSHPLUS_API: Endpoint = Endpoint {
+    env_key: "CFRS_PUSHPLUS_API",
+    default: "http://www.pushplus.plus",
+};
+const SERVERCHAN_API: Endpoint = Endpoint {
+    env_key: "CFRS_SERVERCHAN_API",
+    default: "https://sctapi.ftqq.com",
+};
+const PUSHDEER_API: Endpoint = Endpoint {
+    env_key: "CFRS_PUSHDEER_API",
+    default: "https://api2.pushdeer.com",
+};
+const WECHAT_API: Endpoint = Endpoint {
+    env_key: "CFRS_WECHAT_API",
+    default: "https://qyapi.weixin.qq.com",
+};
+const GITHUB_API: Endpoint = Endpoint {
+    env_key: "CFRS_GITHUB_API",
+    default: "https://api.github.com",
+};
 
 pub struct PushService {
     config_path: PathBuf,
@@ -26,10 +55,10 @@ impl PushService {
         Ok(service)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn run_push(
         &self,
         push_mod: &str,
-        hostnames: &str,
         v4_num: u32,
         v6_num: u32,
         ip_type: &str,
@@ -50,7 +79,7 @@ impl PushService {
 
         print_section_header("推送任务");
 
-        let ip_info = self.process_csv_file(csvfile, hostnames, v4_num, v6_num, ip_type)?;
+        let ip_info = self.process_csv_file(csvfile, domain_ip_mapping, v4_num, v6_num, ip_type)?;
 
         for mode in push_modes {
             let res = match mode {
@@ -69,6 +98,35 @@ impl PushService {
         Ok(())
     }
 
+    /// 推送一条文本通知，GitHub 推送只用于上传IP，不参与通知
+    pub fn push_notice(&self, push_mod: &str, message: &str) -> Result<()> {
+        let push_modes: Vec<&str> = push_mod
+            .split_whitespace()
+            .filter(|&mode| mode != "不设置" && mode != "Github")
+            .collect();
+        if push_modes.is_empty() {
+            info_println(format_args!("根据配置跳过推送"));
+            return Ok(());
+        }
+
+        print_section_header("推送通知");
+
+        for mode in push_modes {
+            let res = match mode {
+                "Telegram" | "PushPlus" | "Server酱" | "PushDeer" | "企业微信"
+                | "Synology-Chat" => self.push_with_config(mode, message),
+                _ => {
+                    warning_println(format_args!("未知的推送模式: {}", mode));
+                    continue;
+                }
+            };
+            self.push_result(mode, res);
+        }
+
+        info_println(format_args!("推送通知完成"));
+        Ok(())
+    }
+
     fn push_result(&self, name: &str, res: Result<()>) {
         match res {
             Ok(_) => success_println(format_args!("{}", name)),
@@ -96,7 +154,7 @@ impl PushService {
     fn process_csv_file(
         &self,
         csvfile: &str,
-        hostnames: &str,
+        domain_ip_mapping: &[(String, String)],
         v4_num: u32,
         v6_num: u32,
         ip_type: &str,
@@ -106,36 +164,37 @@ impl PushService {
         }
 
         let content = fs::read_to_string(csvfile)?;
-        let lines: Vec<&str> = content.lines().collect();
-        if lines.len() <= 1 {
+        // 按配置的测速程序的列名解析
+        let columns = self
+            .config
+            .tester
+            .as_ref()
+            .map(|tester| tester.columns.clone())
+            .unwrap_or_default();
+        let results = match parse_results(&content, &columns) {
+            Ok(results) => results,
+            Err(e) => return Ok(format!("错误: {:#}", e)),
+        };
+        if results.is_empty() {
             return Ok("错误: CSV文件为空或只有标题".to_string());
         }
 
         let ip_count = if ip_type == "IPv4" { v4_num } else { v6_num };
-        let domain_arr: Vec<&str> = hostnames.split_whitespace().collect();
+        let results = filter_results(&results, ip_type, ip_count);
 
-        let csv_data: Vec<Vec<String>> = lines
+        let ips: Vec<String> = results.iter().map(|r| r.ip.to_string()).collect();
+        let latency: Vec<String> = results
             .iter()
-            .skip(1)
-            .take(ip_count as usize)
-            .map(|line| line.split(',').map(|s| s.to_string()).collect::<Vec<_>>())
-            .filter(|fields| fields.len() >= 7)
+            .map(|r| format!("{:.2} ms", r.latency))
             .collect();
-
-        let ips: Vec<&String> = csv_data.iter().map(|f| &f[0]).collect();
-        let latency: Vec<String> = csv_data.iter().map(|f| format!("{} ms", f[4])).collect();
-        let speed: Vec<String> = csv_data
+        let speed: Vec<String> = results
             .iter()
-            .filter_map(|f| {
-                let val = f[5].trim();
-                if !val.is_empty() {
-                    Some(format!("{} MB/s", val))
-                } else {
-                    None
-                }
-            })
+            .filter_map(|r| r.speed.map(|speed| format!("{:.2} MB/s", speed)))
+            .collect();
+        let datacenter: Vec<&str> = results
+            .iter()
+            .map(|r| r.colo.as_deref().unwrap_or_default())
             .collect();
-        let datacenter: Vec<&String> = csv_data.iter().map(|f| &f[6]).collect();
 
         let mut result = String::new();
         result.push_str(&format!("{} 地址：\n", ip_type));
@@ -143,12 +202,18 @@ impl PushService {
             result.push_str(&format!("{}\n", ip));
         }
 
-        result.push_str("━━━━━━━━━━━━━━━━━━━\n域名：\n");
-        for (i, domain) in domain_arr.iter().enumerate() {
-            if i < ips.len() {
-                result.push_str(&format!("{}\n", domain));
+        // 按分配方式列出每个域名解析到的IP，未指定账户组时域名为空
+        let mut domains: Vec<(&str, Vec<&str>)> = Vec::new();
+        for (domain, ip) in domain_ip_mapping.iter().filter(|(d, _)| !d.is_empty()) {
+            match domains.iter_mut().find(|(d, _)| d == domain) {
+                Some((_, ips)) => ips.push(ip),
+                None => domains.push((domain, vec![ip])),
             }
         }
+        result.push_str("━━━━━━━━━━━━━━━━━━━\n域名：\n");
+        for (domain, ips) in &domains {
+            result.push_str(&format!("{}：{}\n", display_name(domain), ips.join(" ")));
+        }
 
         result.push_str("━━━━━━━━━━━━━━━━━━━\n平均延迟：\n");
         for l in &latency {
@@ -168,45 +233,27 @@ impl PushService {
         Ok(result)
     }
 
-    fn curl_request(
+    fn http_request(
         &self,
         method: &str,
         url: &str,
-        data: Option<&str>,
-        headers: &[&str],
+        body: Option<Body>,
+        headers: &[(&str, &str)],
         timeout: u64,
     ) -> Result<(bool, String)> {
-        let mut cmd = Command::new("curl");
-        cmd.arg("-s").arg("--max-time").arg(timeout.to_string());
-        match method {
-            "POST" | "PUT" => {
-                cmd.arg("-X").arg(method);
-                if let Some(d) = data {
-                    cmd.arg("-d").arg(d);
-                }
-                cmd.arg("-H").arg("Content-Type: application/json");
-            }
-            _ => {}
-        }
-        cmd.arg(url);
-        for h in headers {
-            cmd.arg("-H").arg(h);
-        }
-        let output = cmd.output()?;
-        Ok((
-            output.status.success(),
-            String::from_utf8_lossy(&output.stdout).to_string(),
-        ))
+        let client = HttpClient::new("", Duration::from_secs(timeout));
+        let response = client.send(method, url, headers, body)?;
+        Ok((response.is_success(), response.body))
     }
 
     fn telegram_send(&self, config: &crate::PushConfig, message: &str) -> Result<()> {
         if let (Some(token), Some(user_id)) = (&config.telegram_bot_token, &config.telegram_user_id)
         {
-            let url = format!("https://api.telegram.org/bot{}/sendMessage", token);
+            let url = format!("{}/bot{}/sendMessage", TELEGRAM_API.base_url(), token);
             let json_data =
-                serde_json::json!({ "chat_id": user_id, "parse_mode": "HTML", "text": message })
-                    .to_string();
-            let (success, resp) = self.curl_request("POST", &url, Some(&json_data), &[], 20)?;
+                serde_json::json!({ "chat_id": user_id, "parse_mode": "HTML", "text": message });
+            let (success, resp) =
+                self.http_request("POST", &url, Some(Body::Json(&json_data)), &[], 20)?;
             if !success
                 || !serde_json::from_str::<Value>(&resp)
                     .map(|v| v["ok"].as_bool().unwrap_or(false))
@@ -222,12 +269,11 @@ impl PushService {
         if let Some(token) = &config.pushplus_token {
             let json_data = serde_json::json!({
                 "token": token, "title": "Cloudflare优选IP", "content": message, "template": "html"
-            })
-            .to_string();
-            let (success, resp) = self.curl_request(
+            });
+            let (success, resp) = self.http_request(
                 "POST",
-                "http://www.pushplus.plus/send",
-                Some(&json_data),
+                &format!("{}/send", PUSHPLUS_API.base_url()),
+                Some(Body::Json(&json_data)),
                 &[],
                 20,
             )?;
@@ -248,10 +294,10 @@ impl PushService {
                 "title=Cloudflare优选IP&desp={}",
                 urlencoding::encode(message)
             );
-            let (success, resp) = self.curl_request(
+            let (success, resp) = self.http_request(
                 "POST",
-                &format!("https://sctapi.ftqq.com/{}.send", sendkey),
-                Some(&form_data),
+                &format!("{}/{}.send", SERVERCHAN_API.base_url(), sendkey),
+                Some(Body::Form(&form_data)),
                 &[],
                 20,
             )?;
@@ -268,12 +314,17 @@ impl PushService {
 
     fn pushdeer_send(&self, config: &crate::PushConfig, message: &str) -> Result<()> {
         if let Some(pushkey) = &config.pushdeer_pushkey {
-            let url = format!("https://api2.pushdeer.com/message/push?pushkey={}", pushkey);
+            let url = format!(
+                "{}/message/push?pushkey={}",
+                PUSHDEER_API.base_url(),
+                pushkey
+            );
             let form_data = format!(
                 "text=Cloudflare优选IP&desp={}",
                 urlencoding::encode(message)
             );
-            let (success, resp) = self.curl_request("POST", &url, Some(&form_data), &[], 20)?;
+            let (success, resp) =
+                self.http_request("POST", &url, Some(Body::Form(&form_data)), &[], 20)?;
             if !success
                 || !serde_json::from_str::<Value>(&resp)
                     .map(|v| v["code"].as_i64().unwrap_or(-1) == 0)
@@ -293,23 +344,31 @@ impl PushService {
             &config.wechat_userid,
         ) {
             let token_url = format!(
-                "https://qyapi.weixin.qq.com/cgi-bin/gettoken?corpid={}&corpsecret={}",
-                corpid, secret
+                "{}/cgi-bin/gettoken?corpid={}&corpsecret={}",
+                WECHAT_API.base_url(),
+                corpid,
+                secret
             );
-            let (success, resp) = self.curl_request("GET", &token_url, None, &[], 20)?;
+            let (success, resp) = self.http_request("GET", &token_url, None, &[], 20)?;
             if success {
                 if let Ok(json) = serde_json::from_str::<Value>(&resp) {
                     if json["errcode"].as_i64().unwrap_or(-1) == 0 {
                         let access_token = json["access_token"].as_str().unwrap_or("");
                         let send_url = format!(
-                            "https://qyapi.weixin.qq.com/cgi-bin/message/send?access_token={}",
+                            "{}/cgi-bin/message/send?access_token={}",
+                            WECHAT_API.base_url(),
                             access_token
                         );
                         let json_data = serde_json::json!({
                             "touser": userid, "msgtype": "text", "agentid": agentid, "text": { "content": message }
-                        }).to_string();
-                        let (send_success, _) =
-                            self.curl_request("POST", &send_url, Some(&json_data), &[], 20)?;
+                        });
+                        let (send_success, _) = self.http_request(
+                            "POST",
+                            &send_url,
+                            Some(Body::Json(&json_data)),
+                            &[],
+                            20,
+                        )?;
                         if !send_success {
                             error_println(format_args!("企业微信发送消息失败"));
                         }
@@ -326,8 +385,9 @@ impl PushService {
 
     fn synology_chat_send(&self, config: &crate::PushConfig, message: &str) -> Result<()> {
         if let Some(url) = &config.synology_chat_url {
-            let json_data = serde_json::json!({ "text": message }).to_string();
-            let (success, resp) = self.curl_request("POST", url, Some(&json_data), &[], 20)?;
+            let json_data = serde_json::json!({ "text": message });
+            let (success, resp) =
+                self.http_request("POST", url, Some(Body::Json(&json_data)), &[], 20)?;
             if !success
                 || !serde_json::from_str::<Value>(&resp)
                     .map(|v| v["success"].as_bool().unwrap_or(false))
@@ -383,12 +443,18 @@ impl PushService {
                         .join("\n");
 
                     let check_url = format!(
-                        "https://api.github.com/repos/{}/contents/{}",
-                        repo, remote_path
+                        "{}/repos/{}/contents/{}",
+                        GITHUB_API.base_url(),
+                        repo,
+                        remote_path
                     );
-                    let auth_header = format!("Authorization: token {}", token);
+                    let auth_header = format!("token {}", token);
+                    let headers = [
+                        ("Authorization", auth_header.as_str()),
+                        ("Accept", "application/vnd.github.v3+json"),
+                    ];
                     let (check_success, check_resp) =
-                        self.curl_request("GET", &check_url, None, &[&auth_header], 20)?;
+                        self.http_request("GET", &check_url, None, &headers, 20)?;
 
                     if check_success {
                         if let Ok(check_json) = serde_json::from_str::<Value>(&check_resp) {
@@ -414,13 +480,12 @@ impl PushService {
                                 "content": general_purpose::STANDARD.encode(&final_content),
                                 "sha": sha,
                                 "branch": branch
-                            })
-                            .to_string();
-                            let _ = self.curl_request(
+                            });
+                            let _ = self.http_request(
                                 "PUT",
                                 &check_url,
-                                Some(&json_data),
-                                &[&auth_header, "Accept: application/vnd.github.v3+json"],
+                                Some(Body::Json(&json_data)),
+                                &headers,
                                 20,
                             )?;
                         }
@@ -430,13 +495,12 @@ impl PushService {
                             "message": "创建 Cloudflare 优选 IP 文件",
                             "content": general_purpose::STANDARD.encode(&new_content),
                             "branch": branch
-                        })
-                        .to_string();
-                        let _ = self.curl_request(
+                        });
+                        let _ = self.http_request(
                             "PUT",
                             &check_url,
-                            Some(&json_data),
-                            &[&auth_header, "Accept: application/vnd.github.v3+json"],
+                            Some(Body::Json(&json_data)),
+                            &headers,
                             20,
                         )?;
                     }
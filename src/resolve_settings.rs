@@ -1,5 +1,9 @@
+use crate::start::dns_operations::normalize_name;
+use crate::start::tester::Tester;
+use crate::start::utils::full_domain;
 use crate::{
-    CLOUDFLAREST_RUST, Config, Resolve, Settings, UIComponents, clear_screen, impl_settings,
+    Config, DEFAULT_BUILTIN_SAMPLE, Distribution, Resolve, Settings, Target, UIComponents,
+    clear_screen, impl_settings,
 };
 use anyhow::Result;
 use regex::Regex;
@@ -11,6 +15,7 @@ pub fn get_resolve_input(
     config: &Config,
     default_values: Option<&Resolve>,
     domain_regex: &Regex,
+    hostname_regex: &Regex,
     name_regex: &Regex,
 ) -> anyhow::Result<Option<Resolve>> {
     clear_screen()?;
@@ -84,7 +89,7 @@ pub fn get_resolve_input(
             let input = ui.get_text_input(
                 "请输入要解析的一级域名（留空则返回上级）",
                 default_hostname1,
-                |input| input.trim().is_empty() || domain_regex.is_match(input),
+                |input| input.trim().is_empty() || is_valid_domain(domain_regex, input),
             )?;
 
             if input.trim().is_empty() && default_values.is_none() {
@@ -93,7 +98,7 @@ pub fn get_resolve_input(
             if input.trim().is_empty() && default_values.is_some() {
                 break default_hostname1.to_string();
             }
-            if domain_regex.is_match(&input) {
+            if is_valid_domain(domain_regex, &input) {
                 break input;
             } else {
                 ui.show_error("格式不正确")?;
@@ -104,13 +109,13 @@ pub fn get_resolve_input(
             let default_hostname2 = default_values.map(|d| d.hostname2.as_str()).unwrap_or("");
 
             let input = ui.get_text_input(
-                "请输入一个或多个二级域名（不含一级域名，多个则以空格分隔）",
+                "请输入一个或多个二级域名（不含一级域名，@为一级域名本身，*为泛解析，多个则以空格分隔）",
                 default_hostname2,
                 |input| {
                     if input.trim().is_empty() && default_values.is_some() {
                         return true;
                     }
-                    input.split_whitespace().all(|s| domain_regex.is_match(s))
+                    input.split_whitespace().all(|s| is_valid_hostname(hostname_regex, s, &hostname1))
                 },
             )?;
 
@@ -122,7 +127,9 @@ pub fn get_resolve_input(
                 break default_hostname2.to_string();
             }
 
-            let all_valid = input.split_whitespace().all(|s| domain_regex.is_match(s));
+            let all_valid = input
+                .split_whitespace()
+                .all(|s| is_valid_hostname(hostname_regex, s, &hostname1));
 
             if all_valid {
                 break input;
@@ -134,52 +141,138 @@ pub fn get_resolve_input(
         (hostname1, hostname2)
     };
 
+    // 附加解析目标，与主目标共用同一次测速结果
+    let targets = if add_ddns == "未指定" {
+        Vec::new()
+    } else {
+        get_targets(ui, config, default_values, domain_regex, hostname_regex)?
+    };
+
+    // 记录属性：代理、TTL 和备注（未指定账户组时不涉及DNS记录）
+    let (proxied, ttl, comment) = if add_ddns == "未指定" {
+        (false, 1, String::new())
+    } else {
+        get_record_settings(ui, default_values)?
+    };
+
     // IPv4数量和IPv6数量（使用统一的输入函数）
-    let v4_num = get_ip_count(ui, "请输入IPv4解析数量（可设置为0）", default_values.map(|d| d.v4_num));
-    let v6_num = get_ip_count(ui, "请输入IPv6解析数量（可设置为0）", default_values.map(|d| d.v6_num));
+    let v4_num = get_ip_count(
+        ui,
+        "请输入IPv4解析数量（可设置为0）",
+        default_values.map(|d| d.v4_num),
+    );
+    let v6_num = get_ip_count(
+        ui,
+        "请输入IPv6解析数量（可设置为0）",
+        default_values.map(|d| d.v6_num),
+    );
 
-    // CloudflareST 示例输出
-    look_cfst_rules(ui)?;
+    // IP分配方式（未指定账户组时不涉及域名）
+    let distribution = if add_ddns == "未指定" {
+        Distribution::default()
+    } else {
+        match get_distribution(ui, default_values)? {
+            Some(distribution) => distribution,
+            None => return Ok(None),
+        }
+    };
 
-    // CloudflareST 命令输入
-    let cf_command = {
-        let default_cf = default_values.map(|d| d.cf_command.as_str()).unwrap_or("");
+    // 最少新记录数（未指定账户组时不涉及DNS记录）
+    let min_records = if add_ddns == "未指定" {
+        1
+    } else {
+        get_min_records(ui, default_values)?
+    };
 
-        let input = ui.get_text_input(
-            #[cfg(target_os = "windows")]
-            &format!(
-                "请输入CloudflareST传入参数（无需以\".\\{}\"开头）",
-                CLOUDFLAREST_RUST
-            ),
-            #[cfg(any(target_os = "linux", target_os = "macos"))]
-            &format!(
-                "请输入CloudflareST传入参数（无需以\"./{}\"开头）",
-                CLOUDFLAREST_RUST
-            ),
-            default_cf,
-            |_| true, // 允许任何输入
+    // 接管已有记录（未指定账户组时不涉及DNS记录）
+    let adopt_records = if add_ddns == "未指定" {
+        false
+    } else {
+        ui.confirm(
+            "是否接管域名下没有 [CFRS] 标记的已有记录（从旧版本升级时开启）",
+            default_values.map(|d| d.adopt_records).unwrap_or(false),
+        )?
+    };
+
+    // 共享测速配置，使用时不再单独设置测速参数
+    let test_profile = get_test_profile(ui, config, default_values)?;
+
+    let (cf_command, v4_url, v6_url, cidr_sample, builtin_sample) = if !test_profile.is_empty() {
+        default_values
+            .map(|d| {
+                (
+                    d.cf_command.clone(),
+                    d.v4_url.clone(),
+                    d.v6_url.clone(),
+                    d.cidr_sample,
+                    d.builtin_sample,
+                )
+            })
+            .unwrap_or_else(|| {
+                (
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    0,
+                    DEFAULT_BUILTIN_SAMPLE,
+                )
+            })
+    } else {
+        // CloudflareST 示例输出
+        look_cfst_rules(ui)?;
+
+        // CloudflareST 命令输入
+        let tester = Tester::from_config(config.tester.as_ref());
+        let cf_command = {
+            let default_cf = default_values.map(|d| d.cf_command.as_str()).unwrap_or("");
+
+            let input = ui.get_text_input(
+                &format!("请输入测速程序传入参数（无需以\"{}\"开头）", tester.path),
+                default_cf,
+                |_| true, // 允许任何输入
+            )?;
+
+            if input.trim().is_empty() && default_values.is_none() {
+                String::new()
+            } else if !input.trim().is_empty() {
+                input
+            } else {
+                default_cf.to_string()
+            }
+        };
+
+        // URL 读取 IPv4 和 IPv6（使用统一的输入函数）
+        let v4_url = get_url_input_with_validation(
+            ui,
+            "从URL链接获取IPv4地址",
+            default_values.map(|d| d.v4_url.as_str()).unwrap_or(""),
+        )?;
+        let v6_url = get_url_input_with_validation(
+            ui,
+            "从URL链接获取IPv6地址",
+            default_values.map(|d| d.v6_url.as_str()).unwrap_or(""),
         )?;
 
-        if input.trim().is_empty() && default_values.is_none() {
-            String::new()
-        } else if !input.trim().is_empty() {
-            input
-        } else {
-            default_cf.to_string()
-        }
-    };
+        // 验证配置：如果设置了 URL 但没有 -f 或 -ip 参数，提示用户修改
+        let cf_command =
+            validate_cf_command_for_url(ui, cf_command, &v4_url, &v6_url, &tester.input_file_flag)?;
 
-    // URL 读取 IPv4 和 IPv6（使用统一的输入函数）
-    let v4_url = get_url_input_with_validation(ui, "从URL链接获取IPv4地址", default_values.map(|d| d.v4_url.as_str()).unwrap_or(""))?;
-    let v6_url = get_url_input_with_validation(ui, "从URL链接获取IPv6地址", default_values.map(|d| d.v6_url.as_str()).unwrap_or(""))?;
+        // 地址来源中的网段抽样数量
+        let cidr_sample = get_ip_count(
+            ui,
+            "请输入网段每 /24（IPv6 为 /48）抽取的IP数量（0 为不抽样）",
+            default_values.map(|d| d.cidr_sample),
+        );
 
-    // 验证配置：如果设置了 URL 但没有 -f 或 -ip 参数，提示用户修改
-    let cf_command = validate_cf_command_for_url(
-        ui,
-        cf_command,
-        &v4_url,
-        &v6_url,
-    )?;
+        // 没有地址来源时从内置 Cloudflare IP 段抽取的数量
+        let builtin_sample = get_ip_count(
+            ui,
+            "请输入没有地址来源时从内置 Cloudflare IP 段抽取的IP数量（0 为不使用）",
+            Some(default_values.map_or(DEFAULT_BUILTIN_SAMPLE, |d| d.builtin_sample)),
+        );
+
+        (cf_command, v4_url, v6_url, cidr_sample, builtin_sample)
+    };
 
     // 推送方式
     let push_options = [
@@ -233,11 +326,132 @@ pub fn get_resolve_input(
         v4_url,
         v6_url,
         push_mod,
+        proxied,
+        ttl,
+        comment,
+        min_records,
+        adopt_records,
+        distribution,
+        targets,
+        test_profile,
+        cidr_sample,
+        // 地址来源只能在配置文件中编辑，修改时原样保留
+        v4_sources: default_values
+            .map(|d| d.v4_sources.clone())
+            .unwrap_or_default(),
+        v6_sources: default_values
+            .map(|d| d.v6_sources.clone())
+            .unwrap_or_default(),
+        builtin_sample,
     };
 
     Ok(Some(resolve))
 }
 
+/// 获取共享测速配置输入，配置文件中没有测速配置时返回空字符串
+fn get_test_profile(
+    ui: &UIComponents,
+    config: &Config,
+    default_values: Option<&Resolve>,
+) -> Result<String> {
+    let profiles = config.test_profile.as_deref().unwrap_or_default();
+    if profiles.is_empty() {
+        return Ok(String::new());
+    }
+
+    let profile_list: Vec<String> = profiles
+        .iter()
+        .map(|p| format!("- 测速配置: {}（{}）", p.profile_name, p.cf_command))
+        .collect();
+    ui.show_info_list("共享测速配置", &profile_list)?;
+
+    let default_profile = default_values
+        .map(|d| d.test_profile.as_str())
+        .filter(|p| !p.is_empty())
+        .unwrap_or("0");
+    let input = ui.get_text_input(
+        "请输入共享测速配置名称（输入0则单独设置测速参数）",
+        default_profile,
+        |input| input.trim() == "0" || profiles.iter().any(|p| p.profile_name == input.trim()),
+    )?;
+
+    Ok(match input.trim() {
+        "0" => String::new(),
+        name => name.to_string(),
+    })
+}
+
+/// 获取附加解析目标输入（账户组、一级域名和二级域名），输入0结束
+fn get_targets(
+    ui: &UIComponents,
+    config: &Config,
+    default_values: Option<&Resolve>,
+    domain_regex: &Regex,
+    hostname_regex: &Regex,
+) -> Result<Vec<Target>> {
+    let defaults = default_values
+        .map(|d| d.targets.as_slice())
+        .unwrap_or_default();
+    let mut targets = Vec::new();
+
+    loop {
+        let default = defaults.get(targets.len());
+
+        let add_ddns = ui.get_text_input(
+            &format!(
+                "请输入第 {} 个附加解析目标的账户组名称（输入0结束）",
+                targets.len() + 1
+            ),
+            default.map(|t| t.add_ddns.as_str()).unwrap_or("0"),
+            |input| {
+                input.trim() == "0"
+                    || config
+                        .account
+                        .iter()
+                        .any(|a| a.account_name == input.trim())
+            },
+        )?;
+        if add_ddns.trim() == "0" {
+            break;
+        }
+
+        let hostname1 = ui.get_text_input(
+            "请输入该目标的一级域名",
+            default.map(|t| t.hostname1.as_str()).unwrap_or(""),
+            |input| is_valid_domain(domain_regex, input.trim()),
+        )?;
+
+        let hostname2 = ui.get_text_input(
+            "请输入该目标的一个或多个二级域名（@为一级域名本身，*为泛解析，多个则以空格分隔）",
+            default.map(|t| t.hostname2.as_str()).unwrap_or(""),
+            |input| {
+                !input.trim().is_empty()
+                    && input
+                        .split_whitespace()
+                        .all(|s| is_valid_hostname(hostname_regex, s, hostname1.trim()))
+            },
+        )?;
+
+        targets.push(Target {
+            add_ddns: add_ddns.trim().to_string(),
+            hostname1: hostname1.trim().to_string(),
+            hostname2,
+        });
+    }
+
+    Ok(targets)
+}
+
+/// 一级域名格式正确且可以转换为 punycode
+fn is_valid_domain(domain_regex: &Regex, domain: &str) -> bool {
+    domain_regex.is_match(domain) && normalize_name(domain).is_ok()
+}
+
+/// 二级域名格式正确，且与一级域名拼接后可以转换为 punycode
+fn is_valid_hostname(hostname_regex: &Regex, sub: &str, hostname1: &str) -> bool {
+    hostname_regex.is_match(sub) && normalize_name(&full_domain(sub, hostname1)).is_ok()
+}
+
 // 独立函数，用于显示 CloudflareST 规则
 fn look_cfst_rules(ui: &UIComponents) -> anyhow::Result<()> {
     let lines: Vec<String> = [
@@ -269,6 +483,7 @@ pub struct ResolveSettings {
     config: Config,
     ui: UIComponents,
     domain_regex: Regex,
+    hostname_regex: Regex,
     name_regex: Regex,
 }
 
@@ -278,7 +493,9 @@ impl ResolveSettings {
             config_path: config_path.to_path_buf(),
             config: Config::default(),
             ui: UIComponents::new(),
-            domain_regex: Regex::new(r"^[a-zA-Z0-9\u{4e00}-\u{9fa5}.\-]+$").unwrap(),
+            domain_regex: Regex::new(r"^[\p{L}\p{N}.\-]+$").unwrap(),
+            // 二级域名另外允许 "@" 和以 "*" 开头的泛解析
+            hostname_regex: Regex::new(r"^(@|\*|(\*\.)?[\p{L}\p{N}.\-]+)$").unwrap(),
             name_regex: Regex::new(r"^[A-Za-z0-9_]+$").unwrap(),
         };
         settings.load_config()?;
@@ -315,9 +532,21 @@ impl ResolveSettings {
         if let Some(resolves) = &self.config.resolve {
             let info_list: Vec<String> = resolves.iter().enumerate().map(|(i, r)| {
                 format!(
-                    "\n[{}] 账户组：{}\n    解析组：{}\n    一级域名：{}\n    二级域名：{}\n    IPv4数量：{}\n    IPv6数量：{}\n    CloudflareST命令：{}\n    IPv4地址URL：{}\n    IPv6地址URL：{}\n    推送方式：{}",
+                    "\n[{}] 账户组：{}\n    解析组：{}\n    一级域名：{}\n    二级域名：{}\n    IPv4数量：{}\n    IPv6数量：{}\n    CloudflareST命令：{}\n    IPv4地址URL：{}\n    IPv6地址URL：{}\n    推送方式：{}\n    代理：{}\n    TTL：{}\n    备注：{}\n    最少新记录：{}\n    接管已有记录：{}\n    IP分配：{}\n    附加目标：{}\n    测速配置：{}",
                     i + 1, r.add_ddns, r.ddns_name, r.hostname1, r.hostname2, r.v4_num, r.v6_num,
-                    r.cf_command, r.v4_url, r.v6_url, r.push_mod
+                    r.cf_command, r.v4_url, r.v6_url, r.push_mod,
+                    if r.proxied { "开启" } else { "关闭" },
+                    if r.ttl == 1 { "自动".to_string() } else { r.ttl.to_string() },
+                    r.comment,
+                    if r.min_records == 0 { "不回滚".to_string() } else { r.min_records.to_string() },
+                    if r.adopt_records { "是" } else { "否" },
+                    r.distribution,
+                    if r.targets.is_empty() {
+                        "无".to_string()
+                    } else {
+                        r.targets.iter().map(|t| format!("{}（{} {}）", t.add_ddns, t.hostname1, t.hostname2)).collect::<Vec<_>>().join("，")
+                    },
+                    if r.test_profile.is_empty() { "无" } else { &r.test_profile }
                 )
             }).collect();
 
@@ -339,6 +568,7 @@ impl ResolveSettings {
             &self.config,
             None,
             &self.domain_regex,
+            &self.hostname_regex,
             &self.name_regex,
         )? {
             Some(resolve) => resolve,
@@ -456,6 +686,7 @@ impl ResolveSettings {
             &self.config,
             Some(&self.config.resolve.as_ref().unwrap()[selected_index]),
             &self.domain_regex,
+            &self.hostname_regex,
             &self.name_regex,
         )? {
             Some(resolve) => resolve,
@@ -509,6 +740,131 @@ fn get_ip_count(
     }
 }
 
+/// 获取记录属性输入（代理、TTL 和备注）
+fn get_record_settings(
+    ui: &UIComponents,
+    default_values: Option<&Resolve>,
+) -> Result<(bool, u32, String)> {
+    let proxied = ui.confirm(
+        "是否开启 Cloudflare 代理（橙色云朵）",
+        default_values.map(|d| d.proxied).unwrap_or(false),
+    )?;
+
+    // 开启代理时 TTL 由 Cloudflare 自动设置
+    let ttl = if proxied {
+        1
+    } else {
+        let default_ttl = default_values.map(|d| d.ttl).unwrap_or(1).to_string();
+        let input = ui.get_text_input(
+            "请输入TTL（1为自动，或60~86400秒）",
+            &default_ttl,
+            |input| matches!(input.trim().parse::<u32>(), Ok(1) | Ok(60..=86400)),
+        )?;
+        input.trim().parse().unwrap_or(1)
+    };
+
+    let comment = ui.get_text_input(
+        "请输入记录备注（输入0则不设置备注）",
+        default_values
+            .map(|d| d.comment.as_str())
+            .filter(|c| !c.is_empty())
+            .unwrap_or("0"),
+        |_| true,
+    )?;
+    let comment = match comment.trim() {
+        "0" => String::new(),
+        other => other.to_string(),
+    };
+
+    Ok((proxied, ttl, comment))
+}
+
+/// 获取最少新记录数输入，写入的新记录少于此数时回滚
+fn get_min_records(ui: &UIComponents, default_values: Option<&Resolve>) -> Result<u32> {
+    let default_min = default_values
+        .map(|d| d.min_records)
+        .unwrap_or(1)
+        .to_string();
+    let input = ui.get_text_input(
+        "请输入最少需写入的新记录数，不足时回滚（0为不回滚）",
+        &default_min,
+        |input| input.trim().parse::<u32>().is_ok(),
+    )?;
+    Ok(input.trim().parse().unwrap_or(1))
+}
+
+/// 获取IP分配方式输入，按ESC返回 None
+fn get_distribution(
+    ui: &UIComponents,
+    default_values: Option<&Resolve>,
+) -> Result<Option<Distribution>> {
+    let items = [
+        "轮流分配",
+        "每个域名解析全部IP",
+        "按顺序连续分段",
+        "每个域名解析最快的N个IP",
+        "为每个域名指定数量",
+    ];
+    let current = default_values
+        .map(|d| d.distribution.clone())
+        .unwrap_or_default();
+    let default_item = match current {
+        Distribution::RoundRobin => 0,
+        Distribution::All => 1,
+        Distribution::Chunks => 2,
+        Distribution::Best { .. } => 3,
+        Distribution::Counts { .. } => 4,
+    };
+
+    let distribution = match ui.show_menu("请选择IP分配到各域名的方式", &items, default_item)?
+    {
+        Some(0) => Distribution::RoundRobin,
+        Some(1) => Distribution::All,
+        Some(2) => Distribution::Chunks,
+        Some(3) => {
+            let default_count = match &current {
+                Distribution::Best { count } => count.to_string(),
+                _ => "1".to_string(),
+            };
+            let input = ui.get_text_input(
+                "请输入每个域名解析的IP数量",
+                &default_count,
+                |input| input.trim().parse::<u32>().is_ok_and(|n| n > 0),
+            )?;
+            Distribution::Best {
+                count: input.trim().parse().unwrap_or(1),
+            }
+        }
+        Some(_) => {
+            let default_counts = match &current {
+                Distribution::Counts { counts } => counts
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => String::new(),
+            };
+            let input = ui.get_text_input(
+                "请按二级域名顺序输入各自的IP数量（以空格分隔）",
+                &default_counts,
+                |input| {
+                    !input.trim().is_empty()
+                        && input.split_whitespace().all(|n| n.parse::<u32>().is_ok())
+                },
+            )?;
+            Distribution::Counts {
+                counts: input
+                    .split_whitespace()
+                    .filter_map(|n| n.parse().ok())
+                    .collect(),
+            }
+        }
+        None => return Ok(None),
+    };
+
+    Ok(Some(distribution))
+}
+
 /// 获取 URL 输入并验证（支持默认值和重复验证）
 fn get_url_input_with_validation(
     ui: &UIComponents,
@@ -530,21 +886,28 @@ fn get_url_input_with_validation(
     Ok(input)
 }
 
-/// 验证 cf_command 是否包含 -f 参数（当设置了 v4_url 或 v6_url 时）
+/// 验证 cf_command 是否包含测速程序读取文件的参数（如 -f，当设置了 v4_url 或 v6_url 时）
 fn validate_cf_command_for_url(
     ui: &UIComponents,
     mut cf_command: String,
     v4_url: &str,
     v6_url: &str,
+    input_flag: &str,
 ) -> Result<String> {
-    // 1. 快速路径：不需要校验的情况直接返回
-    if (v4_url.is_empty() && v6_url.is_empty()) || cf_command.contains("-f") {
+    // 1. 快速路径：不需要校验的情况直接返回，测速程序不支持读取文件时无法校验
+    if (v4_url.is_empty() && v6_url.is_empty())
+        || input_flag.is_empty()
+        || cf_command.contains(input_flag)
+    {
         return Ok(cf_command);
     }
 
     // 2. 进入交互循环
     loop {
-        ui.show_message("检测到问题：需要使用 -f 参数，来将 URL 的内容写入进去")?;
+        ui.show_message(&format!(
+            "检测到问题：需要使用 {} 参数，来将 URL 的内容写入进去",
+            input_flag
+        ))?;
 
         let input = ui.get_text_input("重新修改", &cf_command, |_| true)?;
         let trimmed = input.trim();
@@ -556,7 +919,7 @@ fn validate_cf_command_for_url(
 
         cf_command = trimmed.to_string();
 
-        if cf_command.contains("-f") {
+        if cf_command.contains(input_flag) {
             return Ok(cf_command);
         }
     }
@@ -0,0 +1,362 @@
+use super::dns_operations::{
+    DnsProvider, DnsRecord, RecordSettings, Zone, absolute_name, indented_error_println,
+    relative_name,
+};
+use super::signing::{hmac_sha1, nonce, percent_encode, unix_timestamp, utc_datetime};
+use crate::Account;
+use crate::http_client::{ALIDNS_API, HttpClient};
+use anyhow::Result;
+use base64::{Engine as _, engine::general_purpose};
+use serde_json::Value;
+use std::time::Duration;
+
+// 接口版本
+const ALIDNS_VERSION: &str = "2015-01-09";
+// 请求超时时间
+const ALIDNS_TIMEOUT: Duration = Duration::from_secs(30);
+// 免费版允许的最小 TTL，配置为自动时也使用此值
+const ALIDNS_MIN_TTL: u32 = 600;
+// 单次列表请求的最大条数
+const ALIDNS_PAGE_SIZE: u64 = 100;
+// 单次记录查询的最大条数
+const ALIDNS_RECORDS_PAGE_SIZE: u64 = 500;
+
+/// 提取阿里云响应中的错误码与错误信息
+fn alidns_error(json: &Value) -> Option<(String, String)> {
+    let code = json["Code"].as_str()?;
+    Some((
+        code.to_string(),
+        json["Message"].as_str().unwrap_or("未知错误").to_string(),
+    ))
+}
+
+/// 将接口返回的记录转换为 DnsRecord
+///
+/// 阿里云DNS 没有代理；TTL 受套餐下限影响，与配置不一致是正常的，因此不返回 TTL 以免被视为需要更新。
+fn record_of(zone: &str, item: &Value) -> Option<DnsRecord> {
+    Some(DnsRecord {
+        id: item["RecordId"].as_str()?.to_string(),
+        record_type: item["Type"].as_str()?.to_string(),
+        name: absolute_name(item["RR"].as_str()?, zone),
+        content: item["Value"].as_str()?.to_string(),
+        comment: item["Remark"]
+            .as_str()
+            .filter(|r| !r.is_empty())
+            .map(ToString::to_string),
+        modified_on: item["UpdateTimestamp"].as_u64().map(|t| t.to_string()),
+        ..Default::default()
+    })
+}
+
+/// 基于阿里云 DNS 的服务商实现，请求使用 RPC 风格的 HMAC-SHA1 签名
+pub struct AliDnsProvider {
+    http: HttpClient,
+    access_key_id: String,
+    access_key_secret: String,
+}
+
+impl AliDnsProvider {
+    pub fn new(account: &Account) -> Self {
+        Self {
+            http: HttpClient::new(&ALIDNS_API.base_url(), ALIDNS_TIMEOUT),
+            access_key_id: account.access_key_id.clone(),
+            access_key_secret: account.access_key_secret.clone(),
+        }
+    }
+
+    /// 拼接公共参数并签名，返回完整的查询字符串
+    fn signed_query(&self, action: &str, params: &[(&str, String)]) -> String {
+        let (year, month, day, hour, minute, second) = utc_datetime(unix_timestamp());
+        let timestamp = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year, month, day, hour, minute, second
+        );
+
+        let mut all: Vec<(&str, String)> = vec![
+            ("Action", action.to_string()),
+            ("Format", "JSON".to_string()),
+            ("Version", ALIDNS_VERSION.to_string()),
+            ("AccessKeyId", self.access_key_id.clone()),
+            ("SignatureMethod", "HMAC-SHA1".to_string()),
+            ("SignatureVersion", "1.0".to_string()),
+            ("SignatureNonce", nonce()),
+            ("Timestamp", timestamp),
+        ];
+        all.extend(params.iter().cloned());
+        all.sort_by(|a, b| a.0.cmp(b.0));
+
+        let canonical = all
+            .iter()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        let string_to_sign = format!("GET&%2F&{}", percent_encode(&canonical));
+        let key = format!("{}&", self.access_key_secret);
+        let signature =
+            general_purpose::STANDARD.encode(hmac_sha1(key.as_bytes(), &string_to_sign));
+
+        format!("{}&Signature={}", canonical, percent_encode(&signature))
+    }
+
+    /// 调用接口，返回响应 JSON
+    fn request(&self, action: &str, params: &[(&str, String)]) -> Result<Value> {
+        let path = format!("/?{}", self.signed_query(action, params));
+        self.http.get(&path, &[])?.json()
+    }
+
+    /// 设置记录备注，带上管理标记
+    fn set_remark(&self, record_id: &str, settings: &RecordSettings) -> Result<()> {
+        let params = [
+            ("RecordId", record_id.to_string()),
+            ("Remark", settings.managed_comment()),
+        ];
+        let json = self.request("UpdateDomainRecordRemark", &params)?;
+        match alidns_error(&json) {
+            None => Ok(()),
+            Some((code, message)) => {
+                Err(anyhow::anyhow!("设置记录备注失败 ({}): {}", code, message))
+            }
+        }
+    }
+
+    /// 逐页读取区域内的 A/AAAA 记录，`filters` 为附加的查询条件
+    fn list_records(
+        &self,
+        action: &str,
+        zone_id: &str,
+        filters: Vec<(&str, String)>,
+    ) -> Result<Vec<DnsRecord>> {
+        let mut records = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let mut params = filters.clone();
+            params.push(("DomainName", zone_id.to_string()));
+            params.push(("PageNumber", page.to_string()));
+            params.push(("PageSize", ALIDNS_RECORDS_PAGE_SIZE.to_string()));
+
+            let json = self
+                .request(action, &params)
+                .map_err(|e| anyhow::anyhow!("获取DNS记录失败: {}", e))?;
+            if let Some((code, message)) = alidns_error(&json) {
+                return Err(anyhow::anyhow!("获取DNS记录失败 ({}): {}", code, message));
+            }
+
+            let items = json["DomainRecords"]["Record"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let total = json["TotalCount"].as_u64().unwrap_or(0);
+            let is_last = items.is_empty() || page * ALIDNS_RECORDS_PAGE_SIZE >= total;
+
+            records.extend(
+                items
+                    .iter()
+                    .filter(|item| matches!(item["Type"].as_str(), Some("A" | "AAAA")))
+                    .filter_map(|item| record_of(zone_id, item)),
+            );
+
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(records)
+    }
+
+    /// 记录的 TTL，开启代理的设置对阿里云 DNS 无效
+    fn ttl(settings: &RecordSettings) -> String {
+        settings.ttl.max(ALIDNS_MIN_TTL).to_string()
+    }
+}
+
+impl DnsProvider for AliDnsProvider {
+    fn name(&self) -> &'static str {
+        "阿里云DNS"
+    }
+
+    fn validate(&self) -> Result<()> {
+        crate::print_section_header("阿里云DNS 账号验证");
+
+        let json = self.request("DescribeDomains", &[("PageSize", "1".to_string())])?;
+        match alidns_error(&json) {
+            None => {
+                crate::success_println(format_args!("阿里云DNS 账号验证成功"));
+                Ok(())
+            }
+            Some((code, message)) => Err(anyhow::anyhow!(
+                "阿里云DNS 账号验证失败 ({}): {}",
+                code,
+                message
+            )),
+        }
+    }
+
+    fn list_zones(&self) -> Result<Vec<Zone>> {
+        let mut zones = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let params = [
+                ("PageNumber", page.to_string()),
+                ("PageSize", ALIDNS_PAGE_SIZE.to_string()),
+            ];
+            let json = self.request("DescribeDomains", &params)?;
+            if let Some((code, message)) = alidns_error(&json) {
+                return Err(anyhow::anyhow!("获取域名列表失败 ({}): {}", code, message));
+            }
+
+            let items: Vec<Zone> = json["Domains"]["Domain"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|item| {
+                    let name = item["DomainName"].as_str()?.to_string();
+                    Some(Zone {
+                        id: name.clone(),
+                        name,
+                    })
+                })
+                .collect();
+            let total = json["TotalCount"].as_u64().unwrap_or(0);
+            let is_last = items.is_empty() || (zones.len() + items.len()) as u64 >= total;
+            zones.extend(items);
+
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(zones)
+    }
+
+    fn get_dns_records(
+        &self,
+        zone_id: &str,
+        domain: &str,
+        record_type: Option<&str>,
+    ) -> Result<Vec<DnsRecord>> {
+        let mut params = vec![("SubDomain", domain.to_string())];
+        if let Some(rt) = record_type {
+            params.push(("Type", rt.to_string()));
+        }
+        self.list_records("DescribeSubDomainRecords", zone_id, params)
+    }
+
+    fn supports_zone_listing(&self) -> bool {
+        true
+    }
+
+    fn list_zone_records(&self, zone_id: &str) -> Result<Vec<DnsRecord>> {
+        self.list_records("DescribeDomainRecords", zone_id, Vec::new())
+    }
+
+    fn delete_dns_record(&self, _zone_id: &str, record_id: &str) -> Result<bool> {
+        match self.request("DeleteDomainRecord", &[("RecordId", record_id.to_string())]) {
+            Ok(json) => match alidns_error(&json) {
+                None => Ok(true),
+                Some((code, message)) => {
+                    indented_error_println(format_args!("删除DNS记录失败 ({}): {}", code, message));
+                    Ok(false)
+                }
+            },
+            Err(e) => {
+                indented_error_println(format_args!("删除DNS记录失败: {}", e));
+                Ok(false)
+            }
+        }
+    }
+
+    fn create_dns_record(
+        &self,
+        zone_id: &str,
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<Option<String>> {
+        let params = [
+            ("DomainName", zone_id.to_string()),
+            ("RR", relative_name(domain, zone_id)),
+            ("Type", record_type.to_string()),
+            ("Value", ip.to_string()),
+            ("TTL", Self::ttl(settings)),
+        ];
+
+        let json = match self.request("AddDomainRecord", &params) {
+            Ok(json) => json,
+            Err(e) => {
+                indented_error_println(format_args!("创建DNS记录失败: {}", e));
+                return Ok(None);
+            }
+        };
+
+        match alidns_error(&json) {
+            None => {
+                let Some(id) = json["RecordId"].as_str().map(ToString::to_string) else {
+                    return Ok(None);
+                };
+                // 新建接口不支持备注，需单独设置；设置失败时撤销新记录，否则之后不会再管理它
+                if let Err(e) = self.set_remark(&id, settings) {
+                    indented_error_println(format_args!("{}", e));
+                    if !self.delete_dns_record(zone_id, &id)? {
+                        indented_error_println(format_args!(
+                            "未能撤销没有标记的记录 {}，请手动删除",
+                            ip
+                        ));
+                    }
+                    return Ok(None);
+                }
+                Ok(Some(id))
+            }
+            // 已有相同记录，不需要更新
+            Some((code, _)) if code == "DomainRecordDuplicate" => {
+                print!("  ");
+                crate::warning_println(format_args!("已有 {} 的记录，不做更新", ip));
+                Ok(None)
+            }
+            Some((code, message)) => {
+                indented_error_println(format_args!("添加DNS记录失败 ({}): {}", code, message));
+                Ok(None)
+            }
+        }
+    }
+
+    fn update_dns_record(
+        &self,
+        zone_id: &str,
+        record_id: &str,
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<bool> {
+        let params = [
+            ("RecordId", record_id.to_string()),
+            ("RR", relative_name(domain, zone_id)),
+            ("Type", record_type.to_string()),
+            ("Value", ip.to_string()),
+            ("TTL", Self::ttl(settings)),
+        ];
+
+        let json = match self.request("UpdateDomainRecord", &params) {
+            Ok(json) => json,
+            Err(e) => {
+                indented_error_println(format_args!("更新DNS记录失败: {}", e));
+                return Ok(false);
+            }
+        };
+
+        if let Some((code, message)) = alidns_error(&json) {
+            indented_error_println(format_args!("更新DNS记录失败 ({}): {}", code, message));
+            return Ok(false);
+        }
+
+        if let Err(e) = self.set_remark(record_id, settings) {
+            indented_error_println(format_args!("{}", e));
+        }
+        Ok(true)
+    }
+}
\ No newline at end of file
@@ -1,87 +1,587 @@
+use super::dns_operations::{
+    BatchUnsupported, DnsProvider, DnsRecord, RecordSettings, Zone, indented_error_println,
+};
+use super::reconcile::{ReconcilePlan, record_type_of};
+use crate::Account;
+use crate::http_client::{Body, CLOUDFLARE_API, HttpClient, HttpResponse, RequestError};
 use anyhow::Result;
-use std::process::Command;
+use serde_json::Value;
+use std::env;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
 
-pub trait CloudflareApi {
-    /// 验证Cloudflare账户
-    fn validate_cloudflare_account(
+// DNS 记录相关请求的超时时间
+const DNS_API_TIMEOUT: Duration = Duration::from_secs(30);
+// 每页读取的DNS记录数
+const DNS_RECORDS_PER_PAGE: u32 = 100;
+
+// 单个请求的最大尝试次数
+const MAX_ATTEMPTS: u32 = 5;
+// 退避的初始等待时间与上限
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+// Cloudflare 表示请求过于频繁的错误码
+const RATE_LIMIT_CODES: [i64; 2] = [971, 1015];
+// Cloudflare 表示接口地址不存在的错误码
+const NO_ROUTE_CODE: i64 = 7000;
+// 每次运行默认允许的请求次数，与 Cloudflare 每 5 分钟 1200 次的限制一致
+const DEFAULT_REQUEST_BUDGET: u32 = 1200;
+
+/// Cloudflare 认证方式
+#[derive(Debug, Clone)]
+pub enum CloudflareAuth {
+    /// 邮箱 + Global API Key
+    GlobalKey { x_email: String, api_key: String },
+    /// 限定权限的 API Token（Bearer）
+    Token(String),
+}
+
+impl From<&Account> for CloudflareAuth {
+    fn from(account: &Account) -> Self {
+        match account.api_token.as_deref() {
+            Some(token) if !token.trim().is_empty() => CloudflareAuth::Token(token.to_string()),
+            _ => CloudflareAuth::GlobalKey {
+                x_email: account.x_email.clone(),
+                api_key: account.api_key.clone(),
+            },
+        }
+    }
+}
+
+/// 单次运行内可发出的 Cloudflare 请求次数，重试也会计入
+pub struct RequestBudget {
+    remaining: AtomicU32,
+}
+
+impl RequestBudget {
+    /// 读取 CFRS_REQUEST_BUDGET 环境变量，未设置时使用默认值
+    pub fn from_env() -> Self {
+        let budget = RequestBudget {
+            remaining: AtomicU32::new(0),
+        };
+        budget.reset();
+        budget
+    }
+
+    /// 开始新的一次运行时重置剩余次数
+    pub fn reset(&self) {
+        let limit = env::var("CFRS_REQUEST_BUDGET")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_REQUEST_BUDGET);
+        self.remaining.store(limit, Ordering::Relaxed);
+    }
+
+    fn take(&self) -> Result<()> {
+        // 并发请求时原子地扣减
+        self.remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .map(|_| ())
+            .map_err(|_| anyhow::anyhow!("本次运行的 Cloudflare 请求次数已用尽"))
+    }
+}
+
+/// Cloudflare API 客户端，负责拼接地址与认证请求头，并对限流与临时错误自动重试
+pub struct CloudflareClient<'a> {
+    http: HttpClient,
+    auth: CloudflareAuth,
+    budget: Option<&'a RequestBudget>,
+}
+
+impl<'a> CloudflareClient<'a> {
+    pub fn new(auth: &CloudflareAuth, timeout: Duration) -> Self {
+        Self {
+            http: HttpClient::new(&CLOUDFLARE_API.base_url(), timeout),
+            auth: auth.clone(),
+            budget: None,
+        }
+    }
+
+    /// 让请求计入本次运行的请求次数
+    pub fn with_budget(mut self, budget: &'a RequestBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// 发送请求，`path` 为相对于 API 根地址的路径
+    ///
+    /// 遇到网络错误、HTTP 429、5xx 或限流错误码时按指数退避重试，
+    /// 响应带有 Retry-After 时按其等待。
+    /// POST 不是幂等的，只在请求未发出或被限流时重试，避免重复新建记录。
+    pub fn send(&self, method: &str, path: &str, body: Option<&Value>) -> Result<HttpResponse> {
+        let idempotent = method != "POST";
+        let mut attempt = 1;
+        loop {
+            if let Some(budget) = self.budget {
+                budget.take()?;
+            }
+
+            let result = self.send_once(method, path, body);
+            let (reason, delay) = match &result {
+                Ok(response) if !is_retryable(response, idempotent) => return result,
+                Ok(response) => (
+                    format!("HTTP {}", response.status),
+                    retry_after(response).unwrap_or_else(|| backoff(attempt)),
+                ),
+                Err(e) if !idempotent && was_sent(e) => return result,
+                Err(e) => (e.to_string(), backoff(attempt)),
+            };
+
+            if attempt >= MAX_ATTEMPTS {
+                return result;
+            }
+
+            crate::warning_println(format_args!(
+                "Cloudflare 请求失败 ({})，{} 秒后重试 ({}/{})",
+                reason,
+                delay.as_secs(),
+                attempt,
+                MAX_ATTEMPTS - 1
+            ));
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    fn send_once(&self, method: &str, path: &str, body: Option<&Value>) -> Result<HttpResponse> {
+        let bearer;
+        let headers = match &self.auth {
+            CloudflareAuth::GlobalKey { x_email, api_key } => [
+                ("X-Auth-Email", x_email.as_str()),
+                ("X-Auth-Key", api_key.as_str()),
+            ],
+            CloudflareAuth::Token(token) => {
+                bearer = format!("Bearer {}", token);
+                [
+                    ("Authorization", bearer.as_str()),
+                    ("Accept", "application/json"),
+                ]
+            }
+        };
+        self.http.send(method, path, &headers, body.map(Body::Json))
+    }
+
+    /// 通过 /user/tokens/verify 校验 API Token 是否有效
+    pub fn verify_token(&self) -> Result<()> {
+        let response = self.send("GET", "/user/tokens/verify", None)?;
+        let json = response.json()?;
+
+        if !json["success"].as_bool().unwrap_or(false) {
+            let (_, error_message) = cloudflare_error(&json);
+            return Err(anyhow::anyhow!(
+                "API Token 验证失败 (HTTP {}): {}",
+                response.status,
+                error_message
+            ));
+        }
+
+        match json["result"]["status"].as_str().unwrap_or("") {
+            "active" => Ok(()),
+            status => Err(anyhow::anyhow!("API Token 状态异常: {}", status)),
+        }
+    }
+
+    /// 列出凭据可见的全部区域（自动翻页）
+    pub fn list_zones(&self) -> Result<Vec<Zone>> {
+        let mut zones = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let path = format!("/zones?per_page=50&page={}", page);
+            let response = self.send("GET", &path, None)?;
+            let json = response.json()?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                let (_, error_message) = cloudflare_error(&json);
+                return Err(anyhow::anyhow!(
+                    "获取区域列表失败 (HTTP {}): {}",
+                    response.status,
+                    error_message
+                ));
+            }
+
+            let items: Vec<Zone> = serde_json::from_value(json["result"].clone())?;
+            let total_pages = json["result_info"]["total_pages"].as_u64().unwrap_or(1);
+            let is_last = items.is_empty() || page >= total_pages;
+            zones.extend(items);
+
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(zones)
+    }
+}
+
+/// 是否为可重试的响应：限流、限流错误码，幂等请求还包括服务端错误
+fn is_retryable(response: &HttpResponse, idempotent: bool) -> bool {
+    if response.status == 429 || (idempotent && response.status >= 500) {
+        return true;
+    }
+    response.json().is_ok_and(|json| {
+        json["errors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .any(|e| RATE_LIMIT_CODES.contains(&e["code"].as_i64().unwrap_or(0)))
+    })
+}
+
+/// 请求是否可能已到达服务端，无法判断时按已到达处理
+fn was_sent(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<RequestError>().is_none_or(|e| e.sent)
+}
+
+/// 解析 Retry-After 响应头（秒数）
+///
+/// HTTP 日期格式的值不做解析，返回 None 后按指数退避等待
+fn retry_after(response: &HttpResponse) -> Option<Duration> {
+    let seconds: u64 = response.header("Retry-After")?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds).min(BACKOFF_MAX))
+}
+
+/// 第 `attempt` 次失败后的退避时间
+fn backoff(attempt: u32) -> Duration {
+    BACKOFF_BASE
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(BACKOFF_MAX)
+}
+
+/// 提取 Cloudflare 响应中的第一条错误信息
+pub fn cloudflare_error(json: &Value) -> (i64, String) {
+    let code = json["errors"][0]["code"].as_i64().unwrap_or(0);
+    let message = json["errors"][0]["message"]
+        .as_str()
+        .unwrap_or("未知错误")
+        .to_string();
+    (code, message)
+}
+
+/// 生成 /dns_records/batch 的请求体
+fn batch_body(plan: &ReconcilePlan, settings: &RecordSettings) -> Value {
+    let deletes: Vec<Value> = plan
+        .delete
+        .iter()
+        .map(|(_, record)| serde_json::json!({ "id": record.id }))
+        .collect();
+    let patches: Vec<Value> = plan
+        .update
+        .iter()
+        .map(|(domain, record, ip)| {
+            let mut body = settings.to_body(domain, record_type_of(ip), ip);
+            body["id"] = Value::String(record.id.clone());
+            body
+        })
+        .collect();
+    let posts: Vec<Value> = plan
+        .create
+        .iter()
+        .map(|(domain, ip)| settings.to_body(domain, record_type_of(ip), ip))
+        .collect();
+
+    serde_json::json!({
+        "deletes": deletes,
+        "patches": patches,
+        "posts": posts
+    })
+}
+
+/// 基于 Cloudflare API 的 DNS 服务商实现
+pub struct CloudflareProvider<'a> {
+    auth: CloudflareAuth,
+    zone_id: String,
+    budget: &'a RequestBudget,
+}
+
+impl<'a> CloudflareProvider<'a> {
+    pub fn new(account: &Account, budget: &'a RequestBudget) -> Self {
+        Self {
+            auth: CloudflareAuth::from(account),
+            zone_id: account.zone_id.trim().to_string(),
+            budget,
+        }
+    }
+
+    fn client(&self, timeout: Duration) -> CloudflareClient<'a> {
+        CloudflareClient::new(&self.auth, timeout).with_budget(self.budget)
+    }
+
+    /// 逐页读取区域内符合查询条件的DNS记录，`query` 为空时读取全部记录
+    fn list_records(&self, zone_id: &str, query: &str) -> Result<Vec<DnsRecord>> {
+        let client = self.client(DNS_API_TIMEOUT);
+        let mut records = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let path = format!(
+                "/zones/{}/dns_records?per_page={}&page={}{}{}",
+                zone_id,
+                DNS_RECORDS_PER_PAGE,
+                page,
+                if query.is_empty() { "" } else { "&" },
+                query
+            );
+            let response = client
+                .send("GET", &path, None)
+                .map_err(|e| anyhow::anyhow!("获取DNS记录失败: {}", e))?;
+            let json: Value = response.json()?;
+
+            if !json["success"].as_bool().unwrap_or(false) {
+                let (_, error_message) = cloudflare_error(&json);
+                return Err(anyhow::anyhow!(
+                    "获取DNS记录失败 (HTTP {}): {}",
+                    response.status,
+                    error_message
+                ));
+            }
+
+            let items: Vec<DnsRecord> = serde_json::from_value(json["result"].clone())
+                .map_err(|e| anyhow::anyhow!("解析DNS记录失败: {}", e))?;
+            let total_pages = json["result_info"]["total_pages"].as_u64().unwrap_or(1);
+            let is_last = items.is_empty() || page >= total_pages;
+            records.extend(items);
+
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(records)
+    }
+}
+
+impl DnsProvider for CloudflareProvider<'_> {
+    fn name(&self) -> &'static str {
+        "Cloudflare"
+    }
+
+    fn validate(&self) -> Result<()> {
+        crate::print_section_header("Cloudflare 账号验证");
+
+        let timeout = std::time::Duration::from_secs(5);
+
+        let client = self.client(timeout);
+        // 未指定区域ID时只校验凭据本身，区域在之后根据域名自动识别
+        let path = if self.zone_id.is_empty() {
+            "/zones?per_page=1".to_string()
+        } else {
+            format!("/zones/{}", self.zone_id)
+        };
+
+        // 网络错误与限流由 send 自动重试
+        let response = client
+            .send("GET", &path, None)
+            .map_err(|e| anyhow::anyhow!("登录失败: {}", e))?;
+        let json = response.json()?;
+
+        if json["success"].as_bool().unwrap_or(false) {
+            crate::success_println(format_args!("Cloudflare 账号验证成功"));
+            Ok(())
+        } else {
+            let (_, error_message) = cloudflare_error(&json);
+            Err(anyhow::anyhow!(
+                "登录失败 (HTTP {}): {}",
+                response.status,
+                error_message
+            ))
+        }
+    }
+
+    fn list_zones(&self) -> Result<Vec<Zone>> {
+        self.client(DNS_API_TIMEOUT).list_zones()
+    }
+
+    fn get_dns_records(
         &self,
-        x_email: &str,
-        api_key: &str,
         zone_id: &str,
-    ) -> Result<()>;
-}
+        domain: &str,
+        record_type: Option<&str>,
+    ) -> Result<Vec<DnsRecord>> {
+        let mut query = format!("name={}", urlencoding::encode(domain));
+        if let Some(rt) = record_type {
+            query.push_str(&format!("&type={}", rt));
+        }
+        self.list_records(zone_id, &query)
+    }
+
+    fn supports_zone_listing(&self) -> bool {
+        true
+    }
+
+    fn list_zone_records(&self, zone_id: &str) -> Result<Vec<DnsRecord>> {
+        let records = self.list_records(zone_id, "")?;
+        Ok(records
+            .into_iter()
+            .filter(|r| matches!(r.record_type.as_str(), "A" | "AAAA"))
+            .collect())
+    }
+
+    fn delete_dns_record(&self, zone_id: &str, record_id: &str) -> Result<bool> {
+        let path = format!("/zones/{}/dns_records/{}", zone_id, record_id);
 
-impl CloudflareApi for super::start_struct::Start {
-    fn validate_cloudflare_account(
+        let client = self.client(DNS_API_TIMEOUT);
+        let response = match client.send("DELETE", &path, None) {
+            Ok(response) => response,
+            Err(e) => {
+                indented_error_println(format_args!("删除DNS记录失败: {}", e));
+                return Ok(false);
+            }
+        };
+
+        let json: Value = match response.json() {
+            Ok(j) => j,
+            Err(e) => {
+                indented_error_println(format_args!("{}", e));
+                return Ok(false);
+            }
+        };
+
+        if json["success"].as_bool().unwrap_or(false) {
+            Ok(true)
+        } else {
+            let (_, error_message) = cloudflare_error(&json);
+            indented_error_println(format_args!(
+                "删除DNS记录失败 (HTTP {}): {}",
+                response.status, error_message
+            ));
+            Ok(false)
+        }
+    }
+
+    fn create_dns_record(
         &self,
-        x_email: &str,
-        api_key: &str,
         zone_id: &str,
-    ) -> Result<()> {
-        crate::print_section_header("Cloudflare 账号验证");
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<Option<String>> {
+        let path = format!("/zones/{}/dns_records", zone_id);
 
-        let max_retries = 10;
-        let retry_delay = std::time::Duration::from_secs(2);
-        let timeout = std::time::Duration::from_secs(5);
+        let body = settings.to_body(domain, record_type, ip);
+
+        let client = self.client(DNS_API_TIMEOUT);
+        let response = match client.send("POST", &path, Some(&body)) {
+            Ok(response) => response,
+            Err(e) => {
+                indented_error_println(format_args!("创建DNS记录失败: {}", e));
+                return Ok(None);
+            }
+        };
+
+        let json: Value = match response.json() {
+            Ok(j) => j,
+            Err(e) => {
+                indented_error_println(format_args!("{}", e));
+                return Ok(None);
+            }
+        };
+
+        let success = json["success"].as_bool().unwrap_or(false);
+
+        if success {
+            Ok(json["result"]["id"].as_str().map(ToString::to_string))
+        } else {
+            let (code, error_message) = cloudflare_error(&json);
+
+            // 如果出现错误代码 81057，表示已有相同记录，不需要更新
+            if code == 81057 {
+                print!("  ");
+                crate::warning_println(format_args!("已有 {} 的记录，不做更新", ip));
+                Ok(None)
+            } else {
+                indented_error_println(format_args!(
+                    "添加DNS记录失败 (HTTP {}): {}",
+                    response.status, error_message
+                ));
+                Ok(None)
+            }
+        }
+    }
+
+    fn update_dns_record(
+        &self,
+        zone_id: &str,
+        record_id: &str,
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<bool> {
+        let path = format!("/zones/{}/dns_records/{}", zone_id, record_id);
 
-        let url = format!("https://api.cloudflare.com/client/v4/zones/{}", zone_id);
-
-        for attempt in 1..=max_retries {
-            crate::info_println(format_args!("第 {} 次登录尝试 ", attempt));
-
-            let output = Command::new("curl")
-                .arg("-s")
-                .arg("--max-time")
-                .arg(timeout.as_secs().to_string())
-                .arg("-H")
-                .arg(format!("X-Auth-Email: {}", x_email))
-                .arg("-H")
-                .arg(format!("X-Auth-Key: {}", api_key))
-                .arg("-H")
-                .arg("Content-Type: application/json")
-                .arg(&url)
-                .output();
-
-            match output {
-                Ok(output) => {
-                    crate::info_println(format_args!("收到 Cloudflare 响应"));
-
-                    if output.status.success() {
-                        let response_text = String::from_utf8_lossy(&output.stdout);
-                        let json: serde_json::Value = serde_json::from_str(&response_text)?;
-
-                        if json["success"].as_bool().unwrap_or(false) {
-                            crate::success_println(format_args!("Cloudflare 账号验证成功"));
-                            return Ok(());
-                        } else {
-                            let error_message =
-                                json["errors"][0]["message"].as_str().unwrap_or("未知错误");
-                            crate::error_println(format_args!(
-                                "第 {} / {} 次登录失败",
-                                attempt, max_retries
-                            ));
-                            crate::error_println(format_args!("错误信息: {}", error_message));
-                        }
-                    } else {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        crate::error_println(format_args!("登录尝试失败，错误: {}", stderr));
-                    }
-                }
-
-                Err(e) => {
-                    crate::error_println(format_args!("登录尝试失败，错误: {}", e));
-                }
+        let body = settings.to_body(domain, record_type, ip);
+
+        let client = self.client(DNS_API_TIMEOUT);
+        let response = match client.send("PATCH", &path, Some(&body)) {
+            Ok(response) => response,
+            Err(e) => {
+                indented_error_println(format_args!("更新DNS记录失败: {}", e));
+                return Ok(false);
             }
+        };
 
-            if attempt < max_retries {
-                crate::warning_println(format_args!("等待 {} 秒后重试...", retry_delay.as_secs()));
-                std::thread::sleep(retry_delay);
+        let json: Value = match response.json() {
+            Ok(j) => j,
+            Err(e) => {
+                indented_error_println(format_args!("{}", e));
+                return Ok(false);
             }
+        };
+
+        if json["success"].as_bool().unwrap_or(false) {
+            Ok(true)
+        } else {
+            let (_, error_message) = cloudflare_error(&json);
+            indented_error_println(format_args!(
+                "更新DNS记录失败 (HTTP {}): {}",
+                response.status, error_message
+            ));
+            Ok(false)
         }
+    }
 
-        Err(anyhow::anyhow!(
-            "登录失败，已达到最大重试次数 {}",
-            max_retries
-        ))
+    fn supports_batch(&self) -> bool {
+        true
+    }
+
+    fn batch_dns_records(
+        &self,
+        zone_id: &str,
+        plan: &ReconcilePlan,
+        settings: &RecordSettings,
+    ) -> Result<Vec<String>> {
+        let path = format!("/zones/{}/dns_records/batch", zone_id);
+
+        let client = self.client(DNS_API_TIMEOUT);
+        let response = client
+            .send("POST", &path, Some(&batch_body(plan, settings)))
+            .map_err(|e| anyhow::anyhow!("批量提交DNS记录失败: {}", e))?;
+        if matches!(response.status, 404 | 405) {
+            return Err(BatchUnsupported.into());
+        }
+        let json: Value = response.json()?;
+
+        if json["success"].as_bool().unwrap_or(false) {
+            Ok(json["result"]["posts"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|post| post["id"].as_str().map(ToString::to_string))
+                .collect())
+        } else {
+            let (code, error_message) = cloudflare_error(&json);
+            if code == NO_ROUTE_CODE {
+                return Err(BatchUnsupported.into());
+            }
+            Err(anyhow::anyhow!(
+                "批量提交DNS记录失败 (HTTP {}): {}",
+                response.status,
+                error_message
+            ))
+        }
     }
 }
\ No newline at end of file
@@ -1,25 +1,44 @@
+use super::dns_operations::{RecordSettings, normalize_name};
 use super::ip_operations::IpOperations;
+use super::start_struct::TestSettings;
+use super::utils::create_targets_ip_mapping;
+use crate::{Account, Distribution, IpSource};
 use anyhow::Result;
 #[cfg(target_os = "linux")]
 use std::process::Command;
 
+/// 一个解析目标：账户及其下要解析的完整域名
+#[derive(Debug, Clone)]
+pub struct DdnsTarget<'a> {
+    pub account: &'a Account,
+    pub domains: Vec<String>,
+}
+
+impl<'a> DdnsTarget<'a> {
+    /// 由一级域名和二级域名拼出完整域名，国际化域名统一转换为 punycode 后再调用接口
+    pub fn new(account: &'a Account, hostname1: &str, hostname2: &str) -> Result<Self> {
+        let domains = hostname2
+            .split_whitespace()
+            .map(|sub| normalize_name(&super::utils::full_domain(sub, hostname1)))
+            .collect::<Result<_>>()?;
+        Ok(Self { account, domains })
+    }
+}
+
 pub trait DdnsOperations {
     /// 运行DDNS更新流程
+    #[allow(clippy::too_many_arguments)]
     fn run_start_ddns(
         &self,
-        add_ddns: &str,
         ddns_name: &str,
-        x_email: &str,
-        zone_id: &str,
-        api_key: &str,
-        hostname1: &str,
-        hostname2: &str,
+        targets: &[DdnsTarget],
         v4_num: u32,
         v6_num: u32,
-        cf_command: &str,
-        v4_url: &str,
-        v6_url: &str,
+        test_settings: &TestSettings,
+        test_profile: Option<&str>,
         push_mod: &str,
+        record_settings: &RecordSettings,
+        distribution: &Distribution,
 
         #[cfg(target_os = "linux")] clien: &str,
     ) -> Result<()>;
@@ -29,17 +48,16 @@ pub trait DdnsOperations {
     fn control_plugin(&self, clien: &str, action: &str) -> Result<Option<&'static str>>;
 
     /// 推送IP地址的辅助函数
+    #[allow(clippy::too_many_arguments)]
     fn push_ips(
         &self,
         push_mod: &str,
-        hostnames: &str,
         v4_num: u32,
         v6_num: u32,
         ip_type: &str,
         ddns_name: &str,
         ips: &[String],
-        domains: &[String],
-        add_ddns: &str,
+        domain_ip_mapping: &[(String, String)],
         cf_command: &str,
     ) -> Result<()>;
 }
@@ -47,44 +65,53 @@ pub trait DdnsOperations {
 impl DdnsOperations for super::start_struct::Start {
     fn run_start_ddns(
         &self,
-        add_ddns: &str,
         ddns_name: &str,
-        x_email: &str,
-        zone_id: &str,
-        api_key: &str,
-        hostname1: &str,
-        hostname2: &str,
+        targets: &[DdnsTarget],
         v4_num: u32,
         v6_num: u32,
-        cf_command: &str,
-        v4_url: &str,
-        v6_url: &str,
+        test_settings: &TestSettings,
+        test_profile: Option<&str>,
         push_mod: &str,
+        record_settings: &RecordSettings,
+        distribution: &Distribution,
 
         #[cfg(target_os = "linux")] clien: &str,
     ) -> Result<()> {
-        let (domains, hostnames) = if add_ddns != "未指定" {
-            let list: Vec<String> = hostname2
-                .split_whitespace()
-                .map(|sub| format!("{}.{}", sub, hostname1))
-                .collect();
-            let joined = list.join(" ");
-            (list, joined)
-        } else {
-            (Vec::new(), String::new())
-        };
+        let cf_command = test_settings.cf_command;
 
-        crate::print_section_header("插件暂停");
+        // 预览模式复用已有测速结果，或共享测速配置本次运行已测速时，不运行测速，也就无需暂停插件
+        let shared_result = test_profile.is_some_and(|profile| {
+            (v4_num > 0 || v6_num > 0)
+                && [("IPv4", v4_num), ("IPv6", v6_num)]
+                    .iter()
+                    .all(|&(ip_type, num)| num == 0 || self.has_test_result(profile, ip_type))
+        });
+        let reuse_result = self.mode.reuse_result() || shared_result;
+
+        if self.mode.reuse_result() {
+            crate::info_println(format_args!("预览模式，复用已有的测速结果"));
+        } else if let Some(profile) = test_profile.filter(|_| shared_result) {
+            crate::info_println(format_args!("复用测速配置 {} 本次运行的测速结果", profile));
+        } else if self.mode.is_plan() {
+            crate::info_println(format_args!("预览模式，只运行测速，不暂停或重启插件"));
+        } else {
+            crate::print_section_header("插件暂停");
+        }
 
+        // 预览模式不改动系统服务，插件保持原状
         #[cfg(target_os = "linux")]
-        let plugin_status = self.control_plugin(clien, "stop")?;
+        let plugin_status = if reuse_result || self.mode.is_plan() {
+            None
+        } else {
+            self.control_plugin(clien, "stop")?
+        };
 
         #[cfg(not(target_os = "linux"))]
-        {
+        if !reuse_result {
             crate::info_println(format_args!("当前系统不需要处理插件"));
         }
 
-        let output_file = super::utils::parse_cf_command_for_file(cf_command, "-f");
+        let output_file = self.tester.input_file(cf_command);
 
         let is_force_read_mode = v4_num == 0 && v6_num == 0;
 
@@ -92,53 +119,89 @@ impl DdnsOperations for super::start_struct::Start {
             crate::info_println(format_args!(
                 "IPv4和IPv6所需数量都设为0，跳过测速并直接推送消息"
             ));
+            let no_source =
+                test_settings.v4_sources.is_empty() || test_settings.v6_sources.is_empty();
+            if no_source && test_settings.builtin_sample > 0 {
+                crate::info_println(format_args!("未运行测速，不使用内置 Cloudflare IP 段"));
+            }
         }
 
-        let handle_ip_process = |ip_type: &str, url: &str, num: u32| -> Result<()> {
+        let handle_ip_process = |ip_type: &str, sources: &[IpSource], num: u32| -> Result<()> {
             if !is_force_read_mode && num == 0 {
                 crate::info_println(format_args!("根据设置，跳过 {} 测速", ip_type));
                 return Ok(());
             }
 
-            let ips = if is_force_read_mode {
-                self.read_ips_from_csv(ip_type, 0, cf_command)?
+            let (ips, pushed_targets) = if is_force_read_mode {
+                (
+                    self.read_ips_from_csv(ip_type, 0, cf_command)?,
+                    targets.to_vec(),
+                )
             } else {
-                let (fetched_ips, _) = self.process_ip_type(
+                let (fetched_ips, _, failures) = self.process_ip_type(
                     ip_type,
-                    url,
+                    sources,
                     num,
+                    test_settings.cidr_sample,
+                    test_settings.builtin_sample,
                     cf_command,
-                    add_ddns,
-                    x_email,
-                    zone_id,
-                    api_key,
-                    &domains,
+                    test_profile,
+                    targets,
+                    record_settings,
+                    distribution,
                     output_file.as_deref(),
                     #[cfg(target_os = "linux")]
-                    plugin_status.as_deref(),
+                    plugin_status,
                     #[cfg(target_os = "linux")]
                     clien,
                 )?;
-                fetched_ips
+
+                // 失败的解析目标推送失败或回滚通知，不再推送其未生效的IP；预览模式不推送
+                let mut succeeded = Vec::new();
+                for (target, failure) in targets.iter().zip(failures) {
+                    match failure {
+                        Some(failure) if !self.mode.is_plan() => {
+                            self.push_notice(push_mod, &failure.message(ddns_name, ip_type))?
+                        }
+                        Some(_) => {}
+                        None => succeeded.push(target.clone()),
+                    }
+                }
+                if succeeded.is_empty() && !targets.is_empty() {
+                    return Ok(());
+                }
+                (fetched_ips, succeeded)
             };
 
-            self.push_ips(
-                push_mod, &hostnames, v4_num, v6_num, ip_type, ddns_name, &ips, &domains, add_ddns,
-                cf_command,
-            )?;
+            if !self.mode.is_plan() {
+                let domain_ip_mapping =
+                    create_targets_ip_mapping(&ips, &pushed_targets, distribution);
+                self.push_ips(
+                    push_mod,
+                    v4_num,
+                    v6_num,
+                    ip_type,
+                    ddns_name,
+                    &ips,
+                    &domain_ip_mapping,
+                    cf_command,
+                )?;
+            }
 
             Ok(())
         };
 
-        handle_ip_process("IPv4", v4_url, v4_num)?;
-        handle_ip_process("IPv6", v6_url, v6_num)?;
+        handle_ip_process("IPv4", &test_settings.v4_sources, v4_num)?;
+        handle_ip_process("IPv6", &test_settings.v6_sources, v6_num)?;
+
+        if self.mode.is_plan() {
+            crate::info_println(format_args!("预览模式，未修改DNS记录，也未推送消息"));
+        }
 
         #[cfg(target_os = "linux")]
-        if clien != "未指定" && !clien.is_empty() {
-            if let Some("stopped") = plugin_status {
-                crate::print_section_header("插件恢复");
-                self.control_plugin(clien, "start")?;
-            }
+        if clien != "未指定" && !clien.is_empty() && plugin_status == Some("stopped") {
+            crate::print_section_header("插件恢复");
+            self.control_plugin(clien, "start")?;
         }
 
         Ok(())
@@ -182,27 +245,22 @@ impl DdnsOperations for super::start_struct::Start {
     fn push_ips(
         &self,
         push_mod: &str,
-        hostnames: &str,
         v4_num: u32,
         v6_num: u32,
         ip_type: &str,
         ddns_name: &str,
         ips: &[String],
-        domains: &[String],
-        add_ddns: &str,
+        domain_ip_mapping: &[(String, String)],
         cf_command: &str,
     ) -> Result<()> {
         if !ips.is_empty() {
             let params = super::start_struct::PushParams {
                 push_mod,
-                hostnames,
                 v4_num,
                 v6_num,
                 ip_type,
                 ddns_name,
-                ips,
-                domains,
-                add_ddns,
+                domain_ip_mapping,
                 csvfile: cf_command,
             };
             self.execute_push(params)?;
@@ -1,247 +1,302 @@
+use super::alidns::AliDnsProvider;
+use super::cloudflare_api::{CloudflareProvider, RequestBudget};
+use super::dnspod::DnsPodProvider;
+use super::reconcile::ReconcilePlan;
+use super::rfc2136::Rfc2136Provider;
+use super::simulate::SimulatedProvider;
+use crate::{Account, ProviderKind, Resolve};
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use std::fmt::Arguments;
-use std::process::Command;
 
-#[derive(Debug, Deserialize)]
+/// 写入记录备注开头的标记，带此标记的记录才由 CFRS 管理
+pub const MANAGED_MARKER: &str = "[CFRS]";
+
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct DnsRecord {
     pub id: String,
+    #[serde(rename = "type", default)]
+    pub record_type: String,
+    #[serde(default)]
+    pub name: String,
     pub content: String,
+    /// 服务商不区分或不返回时为 None
+    #[serde(default)]
+    pub ttl: Option<u32>,
+    #[serde(default)]
+    pub proxied: Option<bool>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub modified_on: Option<String>,
+}
+
+// 接口对未设置的标签返回 null
+fn null_as_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(Option::<Vec<String>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+impl DnsRecord {
+    /// 是否为 CFRS 创建的记录
+    pub fn is_managed(&self) -> bool {
+        self.comment
+            .as_deref()
+            .is_some_and(|c| c.starts_with(MANAGED_MARKER))
+    }
+
+    /// 代理与 TTL 是否与解析组配置一致，服务商未返回的属性不参与比较
+    pub fn matches(&self, settings: &RecordSettings) -> bool {
+        self.proxied.is_none_or(|p| p == settings.proxied)
+            && self.ttl.is_none_or(|t| t == settings.effective_ttl())
+    }
+}
+
+/// 新建或更新记录时附带的属性，来自解析组配置
+#[derive(Debug, Clone)]
+pub struct RecordSettings {
+    pub proxied: bool,
+    pub ttl: u32,
+    pub comment: String,
+    pub tags: Vec<String>,
+    /// 本次至少需要写入的新记录数，不足时回滚（0 为不回滚）
+    pub min_records: u32,
+    /// 没有管理标记的已有记录也视为托管记录
+    pub adopt: bool,
+}
+
+impl RecordSettings {
+    /// 用于恢复旧记录的属性，沿用原记录的代理、TTL、备注与标签
+    pub fn restoring(&self, record: &DnsRecord) -> Self {
+        let comment = record.comment.as_deref().unwrap_or("");
+        Self {
+            proxied: record.proxied.unwrap_or(self.proxied),
+            ttl: record.ttl.unwrap_or(self.ttl),
+            comment: comment
+                .trim_start_matches(MANAGED_MARKER)
+                .trim()
+                .to_string(),
+            tags: record.tags.clone(),
+            ..self.clone()
+        }
+    }
+
+    /// 实际写入的 TTL，开启代理时固定为自动
+    pub fn effective_ttl(&self) -> u32 {
+        if self.proxied { 1 } else { self.ttl }
+    }
+
+    /// 生成请求体
+    pub fn to_body(&self, domain: &str, record_type: &str, ip: &str) -> Value {
+        let mut body = serde_json::json!({
+            "type": record_type,
+            "name": domain,
+            "content": ip,
+            "proxied": self.proxied,
+            "ttl": self.effective_ttl()
+        });
+        body["comment"] = Value::String(self.managed_comment());
+        if !self.tags.is_empty() {
+            body["tags"] = serde_json::json!(self.tags);
+        }
+        body
+    }
+
+    /// 带管理标记的备注
+    pub fn managed_comment(&self) -> String {
+        if self.comment.is_empty() {
+            MANAGED_MARKER.to_string()
+        } else {
+            format!("{} {}", MANAGED_MARKER, self.comment)
+        }
+    }
+}
+
+impl From<&Resolve> for RecordSettings {
+    fn from(resolve: &Resolve) -> Self {
+        Self {
+            proxied: resolve.proxied,
+            ttl: resolve.ttl,
+            comment: resolve.comment.clone(),
+            tags: Vec::new(),
+            min_records: resolve.min_records,
+            adopt: resolve.adopt_records,
+        }
+    }
 }
 
 // 带缩进的错误打印函数，用于统一处理缩进和错误消息
-fn indented_error_println(args: Arguments) {
+pub fn indented_error_println(args: Arguments) {
     print!("  ");
     crate::error_println(args);
 }
 
-pub trait DnsOperations {
+/// DNS 区域（托管的主域名）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Zone {
+    pub id: String,
+    pub name: String,
+}
+
+/// 按最长后缀为域名匹配所属区域
+pub fn match_zone<'a>(zones: &'a [Zone], domain: &str) -> Option<&'a Zone> {
+    let domain = ascii_name(domain);
+    zones
+        .iter()
+        .filter(|zone| {
+            let name = ascii_name(&zone.name);
+            domain == name || domain.ends_with(&format!(".{}", name))
+        })
+        .max_by_key(|zone| zone.name.len())
+}
+
+/// 把域名转换为小写的 punycode 形式，泛解析的 "*" 标签原样保留
+pub fn normalize_name(name: &str) -> Result<String> {
+    let name = name.trim_end_matches('.');
+    let (prefix, rest) = match name.strip_prefix("*.") {
+        Some(rest) => ("*.", rest),
+        None if name == "*" => return Ok(name.to_string()),
+        None => ("", name),
+    };
+    let ascii = idna::domain_to_ascii(rest)
+        .map_err(|_| anyhow::anyhow!("域名 {} 不是有效的国际化域名", name))?;
+    Ok(format!("{}{}", prefix, ascii))
+}
+
+/// 用于比较的域名形式，无法转换时退回小写的原始域名
+fn ascii_name(name: &str) -> String {
+    normalize_name(name).unwrap_or_else(|_| name.trim_end_matches('.').to_ascii_lowercase())
+}
+
+/// 用于输出的 Unicode 域名形式
+pub fn display_name(name: &str) -> String {
+    match idna::domain_to_unicode(name) {
+        (unicode, Ok(())) => unicode,
+        _ => name.to_string(),
+    }
+}
+
+/// 两个域名是否相同，按 punycode 形式比较并忽略大小写和末尾的点，泛解析的 "*" 只与 "*" 相同
+pub fn same_name(a: &str, b: &str) -> bool {
+    ascii_name(a) == ascii_name(b)
+}
+
+/// 域名相对于区域的主机记录，区域本身为 "@"
+pub fn relative_name(domain: &str, zone: &str) -> String {
+    let domain = ascii_name(domain);
+    let zone = ascii_name(zone);
+    if domain == zone {
+        return "@".to_string();
+    }
+    match domain.strip_suffix(&format!(".{}", zone)) {
+        Some(sub) => sub.to_string(),
+        None => domain,
+    }
+}
+
+/// 主机记录对应的完整域名，"@" 为区域本身
+pub fn absolute_name(rr: &str, zone: &str) -> String {
+    if rr.is_empty() || rr == "@" {
+        zone.to_string()
+    } else {
+        format!("{}.{}", rr, zone)
+    }
+}
+
+/// DNS 服务商需要实现的记录操作，`zone_id` 为 `list_zones` 返回的区域ID
+///
+/// 新建、更新和删除可能在多个线程中并发调用，因此要求实现 `Sync`。
+pub trait DnsProvider: Sync {
+    /// 服务商名称
+    fn name(&self) -> &'static str;
+
+    /// 验证账户凭据
+    fn validate(&self) -> Result<()>;
+
+    /// 列出账户下的全部区域
+    fn list_zones(&self) -> Result<Vec<Zone>>;
+
     /// 获取DNS记录
     fn get_dns_records(
         &self,
-        x_email: &str,
-        api_key: &str,
         zone_id: &str,
         domain: &str,
         record_type: Option<&str>,
     ) -> Result<Vec<DnsRecord>>;
 
     /// 删除DNS记录
-    fn delete_dns_record(
-        &self,
-        x_email: &str,
-        api_key: &str,
-        zone_id: &str,
-        record_id: &str,
-    ) -> Result<bool>;
+    fn delete_dns_record(&self, zone_id: &str, record_id: &str) -> Result<bool>;
 
-    /// 创建DNS记录
+    /// 创建DNS记录，成功时返回新记录的ID
     fn create_dns_record(
         &self,
-        x_email: &str,
-        api_key: &str,
         zone_id: &str,
         domain: &str,
         record_type: &str,
         ip: &str,
-    ) -> Result<bool>;
-}
+        settings: &RecordSettings,
+    ) -> Result<Option<String>>;
 
-impl DnsOperations for super::start_struct::Start {
-    fn get_dns_records(
+    /// 原地更新DNS记录的内容
+    fn update_dns_record(
         &self,
-        x_email: &str,
-        api_key: &str,
         zone_id: &str,
+        record_id: &str,
         domain: &str,
-        record_type: Option<&str>,
-    ) -> Result<Vec<DnsRecord>> {
-        let url = if let Some(rt) = record_type {
-            format!(
-                "https://api.cloudflare.com/client/v4/zones/{}/dns_records?type={}&name={}",
-                zone_id, rt, domain
-            )
-        } else {
-            format!(
-                "https://api.cloudflare.com/client/v4/zones/{}/dns_records?name={}",
-                zone_id, domain
-            )
-        };
-
-        let output = Command::new("curl")
-            .arg("-s")
-            .arg("-H")
-            .arg(format!("X-Auth-Email: {}", x_email))
-            .arg("-H")
-            .arg(format!("X-Auth-Key: {}", api_key))
-            .arg("-H")
-            .arg("Content-Type: application/json")
-            .arg(&url)
-            .output()?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("获取DNS记录失败: {}", stderr));
-        }
-
-        let response_text = String::from_utf8_lossy(&output.stdout);
-        let json: Value = serde_json::from_str(&response_text)?;
-
-        if !json["success"].as_bool().unwrap_or(false) {
-            let error_message = json["errors"][0]["message"].as_str().unwrap_or("未知错误");
-            return Err(anyhow::anyhow!("获取DNS记录失败: {}", error_message));
-        }
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<bool>;
 
-        let records: Vec<DnsRecord> = json["result"]
-            .as_array()
-            .unwrap_or(&Vec::new())
-            .iter()
-            .filter_map(|item| {
-                let id = item["id"].as_str()?.to_string();
-                let content = item["content"].as_str()?.to_string();
-                Some(DnsRecord { id, content })
-            })
-            .collect();
+    /// 是否支持一次性读取整个区域的记录
+    fn supports_zone_listing(&self) -> bool {
+        false
+    }
 
-        Ok(records)
+    /// 读取区域内全部 A/AAAA 记录，记录的 `name` 为完整域名
+    fn list_zone_records(&self, _zone_id: &str) -> Result<Vec<DnsRecord>> {
+        Err(anyhow::anyhow!("{} 不支持读取整个区域的记录", self.name()))
     }
 
-    fn delete_dns_record(
-        &self,
-        x_email: &str,
-        api_key: &str,
-        zone_id: &str,
-        record_id: &str,
-    ) -> Result<bool> {
-        let url = format!(
-            "https://api.cloudflare.com/client/v4/zones/{}/dns_records/{}",
-            zone_id, record_id
-        );
-
-        let output = Command::new("curl")
-            .arg("-s")
-            .arg("-X")
-            .arg("DELETE")
-            .arg("-H")
-            .arg(format!("X-Auth-Email: {}", x_email))
-            .arg("-H")
-            .arg(format!("X-Auth-Key: {}", api_key))
-            .arg("-H")
-            .arg("Content-Type: application/json")
-            .arg(&url)
-            .output();
-
-        let output = match output {
-            Ok(out) => out,
-            Err(e) => {
-                indented_error_println(format_args!("删除DNS记录失败: {}", e));
-                return Ok(false);
-            }
-        };
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            indented_error_println(format_args!("删除DNS记录失败: {}", stderr));
-            Ok(false)
-        } else {
-            let response_text = String::from_utf8_lossy(&output.stdout);
-            let json: Value = match serde_json::from_str(&response_text) {
-                Ok(j) => j,
-                Err(e) => {
-                    indented_error_println(format_args!("解析响应JSON失败: {}", e));
-                    return Ok(false);
-                }
-            };
-
-            if json["success"].as_bool().unwrap_or(false) {
-                Ok(true)
-            } else {
-                let error_message = json["errors"][0]["message"].as_str().unwrap_or("未知错误");
-                indented_error_println(format_args!("删除DNS记录失败: {}", error_message));
-                Ok(false)
-            }
-        }
+    /// 是否支持一次性提交整个区域的变更
+    fn supports_batch(&self) -> bool {
+        false
     }
 
-    fn create_dns_record(
+    /// 一次性提交区域内的删除、更新与新建，要么全部成功要么全部失败，返回新建记录的ID
+    ///
+    /// 接口不可用时返回 [`BatchUnsupported`]
+    fn batch_dns_records(
         &self,
-        x_email: &str,
-        api_key: &str,
-        zone_id: &str,
-        domain: &str,
-        record_type: &str,
-        ip: &str,
-    ) -> Result<bool> {
-        let url = format!(
-            "https://api.cloudflare.com/client/v4/zones/{}/dns_records",
-            zone_id
-        );
-
-        let proxy = false; // 默认关闭Cloudflare代理
-
-        let body = serde_json::json!({
-            "type": record_type,
-            "name": domain,
-            "content": ip,
-            "proxied": proxy
-        });
+        _zone_id: &str,
+        _plan: &ReconcilePlan,
+        _settings: &RecordSettings,
+    ) -> Result<Vec<String>> {
+        Err(BatchUnsupported.into())
+    }
+}
 
-        let output = Command::new("curl")
-            .arg("-s")
-            .arg("-X")
-            .arg("POST")
-            .arg("-H")
-            .arg(format!("X-Auth-Email: {}", x_email))
-            .arg("-H")
-            .arg(format!("X-Auth-Key: {}", api_key))
-            .arg("-H")
-            .arg("Content-Type: application/json")
-            .arg("-d")
-            .arg(body.to_string())
-            .arg(&url)
-            .output();
-
-        let output = match output {
-            Ok(out) => out,
-            Err(e) => {
-                indented_error_println(format_args!("创建DNS记录失败: {}", e));
-                return Ok(false);
-            }
-        };
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            indented_error_println(format_args!("创建DNS记录失败: {}", stderr));
-            return Ok(false);
-        }
+/// 服务商没有可用的批量接口，调用方应改为逐条提交
+#[derive(Debug)]
+pub struct BatchUnsupported;
 
-        let response_text = String::from_utf8_lossy(&output.stdout);
-        let json: Value = match serde_json::from_str(&response_text) {
-            Ok(j) => j,
-            Err(e) => {
-                indented_error_println(format_args!("解析响应JSON失败: {}", e));
-                return Ok(false);
-            }
-        };
+impl std::fmt::Display for BatchUnsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "服务商不支持批量提交")
+    }
+}
 
-        let success = json["success"].as_bool().unwrap_or(false);
+impl std::error::Error for BatchUnsupported {}
 
-        if success {
-            Ok(true)
-        } else {
-            let code = json["errors"][0]["code"].as_i64().unwrap_or(0);
-            let error_message = json["errors"][0]["message"].as_str().unwrap_or("未知错误");
-
-            // 如果出现错误代码 81057，表示已有相同记录，不需要更新
-            if code == 81057 {
-                print!("  ");
-                crate::warning_println(format_args!("已有 {} 的记录，不做更新", ip));
-                Ok(false)
-            } else {
-                indented_error_println(format_args!("添加DNS记录失败: {}", error_message));
-                Ok(false)
-            }
-        }
+/// 根据账户的服务商类型创建对应的实现
+pub fn provider_for<'a>(account: &Account, budget: &'a RequestBudget) -> Box<dyn DnsProvider + 'a> {
+    match account.provider {
+        ProviderKind::Cloudflare => Box::new(CloudflareProvider::new(account, budget)),
+        ProviderKind::DnsPod => Box::new(DnsPodProvider::new(account)),
+        ProviderKind::AliDns => Box::new(AliDnsProvider::new(account)),
+        ProviderKind::Rfc2136 => Box::new(Rfc2136Provider::new(account)),
+        ProviderKind::Simulate => Box::new(SimulatedProvider::new(account)),
     }
 }
\ No newline at end of file
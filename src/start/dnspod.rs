@@ -0,0 +1,356 @@
+use super::dns_operations::{
+    DnsProvider, DnsRecord, RecordSettings, Zone, absolute_name, indented_error_println,
+    relative_name,
+};
+use super::signing::{hex, hmac_sha256, sha256_hex, unix_timestamp, utc_datetime};
+use crate::Account;
+use crate::http_client::{Body, DNSPOD_API, HttpClient, host_of};
+use anyhow::Result;
+use serde_json::Value;
+use std::time::Duration;
+
+// 腾讯云 API 3.0 的服务名与版本
+const DNSPOD_SERVICE: &str = "dnspod";
+const DNSPOD_VERSION: &str = "2021-03-23";
+// 请求超时时间
+const DNSPOD_TIMEOUT: Duration = Duration::from_secs(30);
+// 免费套餐允许的最小 TTL，配置为自动时也使用此值
+const DNSPOD_MIN_TTL: u32 = 600;
+// 单次列表请求的最大条数
+const DNSPOD_PAGE_SIZE: u64 = 3000;
+
+/// 提取 DNSPod 响应中的错误码与错误信息
+fn dnspod_error(response: &Value) -> Option<(String, String)> {
+    let error = response.get("Error")?;
+    Some((
+        error["Code"].as_str().unwrap_or("").to_string(),
+        error["Message"].as_str().unwrap_or("未知错误").to_string(),
+    ))
+}
+
+/// 将接口返回的记录转换为 DnsRecord
+///
+/// DNSPod 没有代理；TTL 受套餐下限影响，与配置不一致是正常的，因此不返回 TTL 以免被视为需要更新。
+fn record_of(zone: &str, item: &Value) -> Option<DnsRecord> {
+    Some(DnsRecord {
+        id: item["RecordId"].as_u64()?.to_string(),
+        record_type: item["Type"].as_str()?.to_string(),
+        name: absolute_name(item["Name"].as_str()?, zone),
+        content: item["Value"].as_str()?.to_string(),
+        comment: item["Remark"]
+            .as_str()
+            .filter(|r| !r.is_empty())
+            .map(ToString::to_string),
+        modified_on: item["UpdatedOn"].as_str().map(ToString::to_string),
+        ..Default::default()
+    })
+}
+
+/// 基于腾讯云 API 3.0 的 DNSPod 服务商实现，请求使用 TC3-HMAC-SHA256 签名
+pub struct DnsPodProvider {
+    http: HttpClient,
+    host: String,
+    secret_id: String,
+    secret_key: String,
+}
+
+impl DnsPodProvider {
+    pub fn new(account: &Account) -> Self {
+        let base_url = DNSPOD_API.base_url();
+        Self {
+            host: host_of(&base_url).to_string(),
+            http: HttpClient::new(&base_url, DNSPOD_TIMEOUT),
+            secret_id: account.access_key_id.clone(),
+            secret_key: account.access_key_secret.clone(),
+        }
+    }
+
+    /// 生成 TC3-HMAC-SHA256 签名的 Authorization 头
+    fn authorization(&self, timestamp: u64, payload: &str) -> String {
+        let (year, month, day, ..) = utc_datetime(timestamp);
+        let date = format!("{:04}-{:02}-{:02}", year, month, day);
+        let scope = format!("{}/{}/tc3_request", date, DNSPOD_SERVICE);
+
+        let canonical_request = format!(
+            "POST\n/\n\ncontent-type:application/json\nhost:{}\n\ncontent-type;host\n{}",
+            self.host,
+            sha256_hex(payload)
+        );
+        let string_to_sign = format!(
+            "TC3-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            sha256_hex(&canonical_request)
+        );
+
+        let secret_date = hmac_sha256(format!("TC3{}", self.secret_key).as_bytes(), &date);
+        let secret_service = hmac_sha256(&secret_date, DNSPOD_SERVICE);
+        let secret_signing = hmac_sha256(&secret_service, "tc3_request");
+        let signature = hex(&hmac_sha256(&secret_signing, &string_to_sign));
+
+        format!(
+            "TC3-HMAC-SHA256 Credential={}/{}, SignedHeaders=content-type;host, Signature={}",
+            self.secret_id, scope, signature
+        )
+    }
+
+    /// 调用接口，返回响应中的 Response 对象
+    fn request(&self, action: &str, params: &Value) -> Result<Value> {
+        let payload = params.to_string();
+        let timestamp = unix_timestamp();
+        let authorization = self.authorization(timestamp, &payload);
+        let timestamp = timestamp.to_string();
+        let headers = [
+            ("Authorization", authorization.as_str()),
+            ("X-TC-Action", action),
+            ("X-TC-Timestamp", timestamp.as_str()),
+            ("X-TC-Version", DNSPOD_VERSION),
+        ];
+
+        let response = self
+            .http
+            .send("POST", "/", &headers, Some(Body::Json(params)))?;
+        let json = response.json()?;
+        Ok(json["Response"].clone())
+    }
+
+    /// 逐页读取区域内的 A/AAAA 记录，`subdomain` 为空时读取整个区域
+    fn list_records(
+        &self,
+        zone_id: &str,
+        subdomain: Option<&str>,
+        record_type: Option<&str>,
+    ) -> Result<Vec<DnsRecord>> {
+        let mut records = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let mut params = serde_json::json!({
+                "Domain": zone_id,
+                "Offset": offset,
+                "Limit": DNSPOD_PAGE_SIZE
+            });
+            if let Some(subdomain) = subdomain {
+                params["Subdomain"] = Value::String(subdomain.to_string());
+            }
+            if let Some(rt) = record_type {
+                params["RecordType"] = Value::String(rt.to_string());
+            }
+
+            let response = self
+                .request("DescribeRecordList", &params)
+                .map_err(|e| anyhow::anyhow!("获取DNS记录失败: {}", e))?;
+
+            let items = match dnspod_error(&response) {
+                // 没有任何记录时接口返回错误
+                Some((code, _)) if code == "ResourceNotFound.NoDataOfRecord" => break,
+                Some((code, message)) => {
+                    return Err(anyhow::anyhow!("获取DNS记录失败 ({}): {}", code, message));
+                }
+                None => response["RecordList"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+            let total = response["RecordCountInfo"]["TotalCount"]
+                .as_u64()
+                .unwrap_or(0);
+            offset += items.len() as u64;
+            let is_last = items.is_empty() || offset >= total;
+
+            records.extend(
+                items
+                    .iter()
+                    .filter(|item| matches!(item["Type"].as_str(), Some("A" | "AAAA")))
+                    .filter_map(|item| record_of(zone_id, item)),
+            );
+
+            if is_last {
+                break;
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// 记录的 TTL，开启代理的设置对 DNSPod 无效
+    fn ttl(settings: &RecordSettings) -> u32 {
+        settings.ttl.max(DNSPOD_MIN_TTL)
+    }
+}
+
+impl DnsProvider for DnsPodProvider {
+    fn name(&self) -> &'static str {
+        "DNSPod"
+    }
+
+    fn validate(&self) -> Result<()> {
+        crate::print_section_header("DNSPod 账号验证");
+
+        let response = self.request("DescribeDomainList", &serde_json::json!({ "Limit": 1 }))?;
+        match dnspod_error(&response) {
+            None => {
+                crate::success_println(format_args!("DNSPod 账号验证成功"));
+                Ok(())
+            }
+            Some((code, message)) => Err(anyhow::anyhow!(
+                "DNSPod 账号验证失败 ({}): {}",
+                code,
+                message
+            )),
+        }
+    }
+
+    fn list_zones(&self) -> Result<Vec<Zone>> {
+        let mut zones = Vec::new();
+
+        loop {
+            let params = serde_json::json!({
+                "Offset": zones.len(),
+                "Limit": DNSPOD_PAGE_SIZE
+            });
+            let response = self.request("DescribeDomainList", &params)?;
+            if let Some((code, message)) = dnspod_error(&response) {
+                return Err(anyhow::anyhow!("获取域名列表失败 ({}): {}", code, message));
+            }
+
+            let items: Vec<Zone> = response["DomainList"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|item| {
+                    let name = item["Name"].as_str()?.to_string();
+                    Some(Zone {
+                        id: name.clone(),
+                        name,
+                    })
+                })
+                .collect();
+            let total = response["DomainCountInfo"]["AllTotal"]
+                .as_u64()
+                .unwrap_or(0);
+            let is_last = items.is_empty() || (zones.len() + items.len()) as u64 >= total;
+            zones.extend(items);
+
+            if is_last {
+                break;
+            }
+        }
+
+        Ok(zones)
+    }
+
+    fn get_dns_records(
+        &self,
+        zone_id: &str,
+        domain: &str,
+        record_type: Option<&str>,
+    ) -> Result<Vec<DnsRecord>> {
+        self.list_records(zone_id, Some(&relative_name(domain, zone_id)), record_type)
+    }
+
+    fn supports_zone_listing(&self) -> bool {
+        true
+    }
+
+    fn list_zone_records(&self, zone_id: &str) -> Result<Vec<DnsRecord>> {
+        self.list_records(zone_id, None, None)
+    }
+
+    fn delete_dns_record(&self, zone_id: &str, record_id: &str) -> Result<bool> {
+        let params = serde_json::json!({
+            "Domain": zone_id,
+            "RecordId": record_id.parse::<u64>().unwrap_or(0)
+        });
+
+        match self.request("DeleteRecord", &params) {
+            Ok(response) => match dnspod_error(&response) {
+                None => Ok(true),
+                Some((code, message)) => {
+                    indented_error_println(format_args!("删除DNS记录失败 ({}): {}", code, message));
+                    Ok(false)
+                }
+            },
+            Err(e) => {
+                indented_error_println(format_args!("删除DNS记录失败: {}", e));
+                Ok(false)
+            }
+        }
+    }
+
+    fn create_dns_record(
+        &self,
+        zone_id: &str,
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<Option<String>> {
+        let params = serde_json::json!({
+            "Domain": zone_id,
+            "SubDomain": relative_name(domain, zone_id),
+            "RecordType": record_type,
+            "RecordLine": "默认",
+            "Value": ip,
+            "TTL": Self::ttl(settings),
+            "Remark": settings.managed_comment()
+        });
+
+        let response = match self.request("CreateRecord", &params) {
+            Ok(response) => response,
+            Err(e) => {
+                indented_error_println(format_args!("创建DNS记录失败: {}", e));
+                return Ok(None);
+            }
+        };
+
+        match dnspod_error(&response) {
+            None => Ok(response["RecordId"].as_u64().map(|id| id.to_string())),
+            // 已有相同记录，不需要更新
+            Some((code, _)) if code == "InvalidParameter.DomainRecordExist" => {
+                print!("  ");
+                crate::warning_println(format_args!("已有 {} 的记录，不做更新", ip));
+                Ok(None)
+            }
+            Some((code, message)) => {
+                indented_error_println(format_args!("添加DNS记录失败 ({}): {}", code, message));
+                Ok(None)
+            }
+        }
+    }
+
+    fn update_dns_record(
+        &self,
+        zone_id: &str,
+        record_id: &str,
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<bool> {
+        let params = serde_json::json!({
+            "Domain": zone_id,
+            "RecordId": record_id.parse::<u64>().unwrap_or(0),
+            "SubDomain": relative_name(domain, zone_id),
+            "RecordType": record_type,
+            "RecordLine": "默认",
+            "Value": ip,
+            "TTL": Self::ttl(settings),
+            "Remark": settings.managed_comment()
+        });
+
+        match self.request("ModifyRecord", &params) {
+            Ok(response) => match dnspod_error(&response) {
+                None => Ok(true),
+                Some((code, message)) => {
+                    indented_error_println(format_args!("更新DNS记录失败 ({}): {}", code, message));
+                    Ok(false)
+                }
+            },
+            Err(e) => {
+                indented_error_println(format_args!("更新DNS记录失败: {}", e));
+                Ok(false)
+            }
+        }
+    }
+}
\ No newline at end of file
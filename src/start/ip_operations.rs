@@ -1,22 +1,48 @@
-use super::dns_operations::DnsOperations;
+use super::ddns_operations::DdnsTarget;
+use super::dns_operations::{RecordSettings, provider_for};
+use super::ip_source::{
+    cache_path, cloudflare_ranges, collect_entries, has_entries, merge_entries, sample_ranges,
+};
+use super::reconcile::{ReconcileOperations, TargetFailure, plan_reconciliation};
+use super::test_result::filter_results;
+use super::zone_operations::ZoneOperations;
+use crate::http_client::HttpClient;
+use crate::{Distribution, IpSource};
 use anyhow::Result;
 use std::fs;
 use std::process::Command;
 use std::time::Duration;
 
 // 为复杂的返回类型添加类型别名
-type IpDomainMapping = (Vec<String>, std::collections::HashMap<String, Vec<String>>);
+type IpDomainMapping = (
+    Vec<String>,
+    std::collections::HashMap<String, Vec<String>>,
+    Vec<Option<TargetFailure>>,
+);
+
+// 单个解析目标的更新结果
+type TargetOutcome = (
+    std::collections::HashMap<String, Vec<String>>,
+    Option<TargetFailure>,
+);
 
 pub trait IpOperations {
-    /// 获取并过滤IP地址
+    /// 获取各来源的IP地址，过滤后按权重合并
     fn fetch_and_filter_ips(
         &self,
-        url: &str,
+        sources: &[IpSource],
         max_count: u32,
+        cidr_sample: u32,
         ip_type: &str,
         output_file: Option<&str>,
     ) -> Result<Vec<String>>;
 
+    /// 读取单个地址来源的内容，URL 获取失败时使用缓存
+    fn load_ip_source(&self, source: &IpSource, ip_type: &str) -> Result<String>;
+
+    /// 从 Cloudflare IP 段中随机抽样，写入 -f 文件
+    fn write_builtin_pool(&self, ip_type: &str, count: u32, output_file: &str) -> Result<()>;
+
     /// 带重试的获取函数
     fn fetch_with_retry(
         &self,
@@ -30,17 +56,19 @@ pub trait IpOperations {
     fn read_ips_from_csv(&self, ip_type: &str, num: u32, cf_command: &str) -> Result<Vec<String>>;
 
     /// 处理单个IP类型的完整流程
+    #[allow(clippy::too_many_arguments)]
     fn process_ip_type(
         &self,
         ip_type: &str,
-        url: &str,
+        sources: &[IpSource],
         num: u32,
+        cidr_sample: u32,
+        builtin_sample: u32,
         cf_command: &str,
-        add_ddns: &str,
-        x_email: &str,
-        zone_id: &str,
-        api_key: &str,
-        domains: &[String],
+        test_profile: Option<&str>,
+        targets: &[DdnsTarget],
+        record_settings: &RecordSettings,
+        distribution: &Distribution,
         output_file: Option<&str>,
 
         #[cfg(target_os = "linux")] plugin_status: Option<&str>,
@@ -48,58 +76,146 @@ pub trait IpOperations {
         #[cfg(target_os = "linux")] clien: &str,
     ) -> Result<IpDomainMapping>;
 
-    /// 获取 CloudflareST-Rust 可执行文件路径
-    fn get_cfst_path(&self) -> String;
+    /// 按测速结果更新一个解析目标的DNS记录，写入不足时回滚
+    fn update_target(
+        &self,
+        target: &DdnsTarget,
+        ips: &[String],
+        record_type: Option<&str>,
+        record_settings: &RecordSettings,
+        distribution: &Distribution,
+    ) -> Result<TargetOutcome>;
+
+    /// 共享测速配置本次运行是否已有指定IP类型的测速结果
+    fn has_test_result(&self, test_profile: &str, ip_type: &str) -> bool;
 }
 
 impl IpOperations for super::start_struct::Start {
     fn fetch_and_filter_ips(
         &self,
-        url: &str,
+        sources: &[IpSource],
         max_count: u32,
+        cidr_sample: u32,
         ip_type: &str,
         output_file: Option<&str>,
     ) -> Result<Vec<String>> {
         crate::info_println(format_args!("获取{}地址...", ip_type));
 
-        if url.is_empty() {
-            crate::warning_println(format_args!("URL为空，跳过{}地址下载", ip_type));
+        if sources.is_empty() {
+            crate::warning_println(format_args!("未设置地址来源，跳过{}地址下载", ip_type));
             return Ok(Vec::new());
         }
 
-        let max_retries = 5;
-        let retry_delay = Duration::from_secs(2);
+        // 解析并过滤各来源的IP地址和网段，单个来源失败时跳过
+        let mut lists = Vec::new();
+        for source in sources {
+            let text = match self.load_ip_source(source, ip_type) {
+                Ok(text) => text,
+                Err(e) => {
+                    crate::warning_println(format_args!("{} 获取失败，已跳过: {}", source, e));
+                    continue;
+                }
+            };
+
+            let limit = match source.limit {
+                0 => max_count,
+                limit => limit.min(max_count),
+            };
+            let summary = collect_entries(&text, ip_type == "IPv4", cidr_sample, limit as usize);
+            if summary.invalid > 0 || summary.reserved > 0 || summary.duplicate > 0 {
+                crate::warning_println(format_args!(
+                    "{}：已忽略 {} 行无法识别的内容、{} 个保留地址、{} 个重复地址",
+                    source, summary.invalid, summary.reserved, summary.duplicate
+                ));
+            }
+            lists.push((summary.entries, source.weight));
+        }
 
-        let response_text = self.fetch_with_retry(url, max_retries, retry_delay, ip_type)?;
+        let (entries, duplicate) = merge_entries(&lists, max_count as usize);
+        if duplicate > 0 {
+            crate::info_println(format_args!("合并时去除 {} 个重复地址", duplicate));
+        }
+        if entries.is_empty() {
+            return Err(anyhow::anyhow!("未从地址来源获取到可用的{}地址", ip_type));
+        }
 
-        // 过滤IP地址
-        let filtered_ips: Vec<String> = response_text
-            .lines()
-            .filter_map(|line| {
-                let line = line.trim();
-                if line.is_empty() {
-                    return None;
-                }
-                let is_ipv4 = line.contains(".");
-                if (ip_type == "IPv4" && is_ipv4) || (ip_type == "IPv6" && !is_ipv4) {
-                    Some(line.to_string())
-                } else {
-                    None
-                }
-            })
-            .take(max_count as usize)
-            .collect();
+        let filtered_ips: Vec<String> = entries.iter().map(ToString::to_string).collect();
 
         // 如果指定了输出文件，则将结果保存到文件
         if let Some(file_path) = output_file {
             let content = filtered_ips.join("\n");
             std::fs::write(file_path, content)?;
-            crate::info_println(format_args!("地址获取成功"));
+            crate::info_println(format_args!("地址获取成功，共 {} 条", filtered_ips.len()));
         }
 
         Ok(filtered_ips)
     }
 
+    fn load_ip_source(&self, source: &IpSource, ip_type: &str) -> Result<String> {
+        match (
+            source.url.is_empty(),
+            source.file.is_empty(),
+            source.cidrs.is_empty(),
+        ) {
+            (false, true, true) => {
+                let cache = cache_path(&source.url);
+                let fetched = self
+                    .fetch_with_retry(&source.url, 5, Duration::from_secs(2), ip_type)
+                    .and_then(|text| {
+                        if has_entries(&text) {
+                            Ok(text)
+                        } else {
+                            Err(anyhow::anyhow!("返回内容中没有可识别的地址"))
+                        }
+                    });
+
+                match fetched {
+                    Ok(text) => {
+                        // 缓存写入失败不影响本次测速
+                        if let Err(e) = cache
+                            .parent()
+                            .map_or(Ok(()), fs::create_dir_all)
+                            .and_then(|_| fs::write(&cache, &text))
+                        {
+                            crate::warning_println(format_args!("缓存 {} 失败: {}", source, e));
+                        }
+                        Ok(text)
+                    }
+                    Err(e) if cache.exists() => {
+                        crate::warning_println(format_args!(
+                            "{} 获取失败（{}），使用上次成功获取的缓存",
+                            source, e
+                        ));
+                        Ok(fs::read_to_string(&cache)?)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            (true, false, true) => fs::read_to_string(&source.file)
+                .map_err(|e| anyhow::anyhow!("读取文件 {} 失败: {}", source.file, e)),
+            (true, true, false) => Ok(source.cidrs.join("\n")),
+            _ => Err(anyhow::anyhow!(
+                "地址来源需要且只能设置 url、file、cidrs 中的一项"
+            )),
+        }
+    }
+
+    fn write_builtin_pool(&self, ip_type: &str, count: u32, output_file: &str) -> Result<()> {
+        let ipv4 = ip_type == "IPv4";
+        let ranges = cloudflare_ranges(self.custom_ranges(ipv4), ipv4);
+        let sampled = sample_ranges(&ranges, count as usize);
+
+        let content: Vec<String> = sampled.iter().map(ToString::to_string).collect();
+        fs::write(output_file, content.join("\n"))?;
+        crate::info_println(format_args!(
+            "未设置{}地址来源，从 {} 个 Cloudflare IP 段中随机抽取 {} 个地址",
+            ip_type,
+            ranges.len(),
+            content.len()
+        ));
+        Ok(())
+    }
+
     fn fetch_with_retry(
         &self,
         url: &str,
@@ -107,24 +223,18 @@ impl IpOperations for super::start_struct::Start {
         retry_delay: Duration,
         ip_type: &str,
     ) -> Result<String> {
+        let client = HttpClient::new("", Duration::from_secs(3));
         let mut attempt = 1;
 
         while attempt <= max_retries {
-            let output = Command::new("curl")
-                .arg("-s")
-                .arg("--max-time")
-                .arg("3")
-                .arg(url)
-                .output();
-
-            match output {
-                Ok(output) => {
-                    if output.status.success() {
-                        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+            match client.get(url, &[]) {
+                Ok(response) => {
+                    if response.is_success() {
+                        return Ok(response.body);
                     } else {
                         crate::warning_println(format_args!(
-                            "获取{}地址失败, 重试 {} 次...",
-                            ip_type, attempt
+                            "获取{}地址失败 (HTTP {}), 重试 {} 次...",
+                            ip_type, response.status, attempt
                         ));
                     }
                 }
@@ -147,49 +257,33 @@ impl IpOperations for super::start_struct::Start {
     }
 
     fn read_ips_from_csv(&self, ip_type: &str, num: u32, cf_command: &str) -> Result<Vec<String>> {
-        let result_csv_path = super::utils::get_result_csv_path(cf_command);
-        if std::path::Path::new(&result_csv_path).exists() {
-            let content = fs::read_to_string(&result_csv_path)?;
-
-            Ok(content
-                .lines()
-                .skip(1) // 跳过标题行
-                .filter_map(|line| {
-                    let fields: Vec<&str> = line.split(',').collect();
-                    fields.first().map(ToString::to_string)
-                })
-                .filter(|ip| {
-                    let is_ipv4 = ip.contains('.');
-                    ip_type.is_empty()
-                        || (ip_type == "IPv4" && is_ipv4)
-                        || (ip_type == "IPv6" && !is_ipv4)
-                })
-                .take(if num == 0 { usize::MAX } else { num as usize })
-                .collect())
-        } else {
-            Ok(Vec::new())
-        }
+        let results = self.tester.read_results(cf_command)?;
+        Ok(filter_results(&results, ip_type, num)
+            .iter()
+            .map(|result| result.ip.to_string())
+            .collect())
     }
 
     fn process_ip_type(
         &self,
         ip_type: &str,
-        url: &str,
+        sources: &[IpSource],
         num: u32,
+        cidr_sample: u32,
+        builtin_sample: u32,
         cf_command: &str,
-        add_ddns: &str,
-        x_email: &str,
-        zone_id: &str,
-        api_key: &str,
-        domains: &[String],
+        test_profile: Option<&str>,
+        targets: &[DdnsTarget],
+        record_settings: &RecordSettings,
+        distribution: &Distribution,
         output_file: Option<&str>,
 
         #[cfg(target_os = "linux")] plugin_status: Option<&str>,
 
         #[cfg(target_os = "linux")] clien: &str,
     ) -> Result<IpDomainMapping> {
-        let mut ips = Vec::new();
         let mut domain_ip_map = std::collections::HashMap::new();
+        let mut failures = Vec::new();
         let record_type = if ip_type.is_empty() {
             None
         } else if ip_type == "IPv4" {
@@ -198,69 +292,78 @@ impl IpOperations for super::start_struct::Start {
             Some("AAAA")
         };
 
-        // 下载IP地址
-        if let Some(file) = output_file
-            && !url.is_empty()
-        {
-            self.fetch_and_filter_ips(url, num, ip_type, Some(file))?;
-        }
-
-        crate::print_section_header("运行测速程序");
-
-        // 获取 CloudflareST-Rust 可执行文件路径
-        let cfst_path = self.get_cfst_path();
+        let result_csv_path = self.tester.result_path(cf_command);
+
+        // 共享测速配置在本次运行中每种IP类型只测速一次
+        let test_key = test_profile.map(|profile| format!("{}/{}", profile, ip_type));
+        let cached = test_key
+            .as_ref()
+            .and_then(|key| self.test_results.borrow().get(key).cloned());
+
+        if let Some(content) = cached {
+            // 结果文件可能已被之后的测速覆盖，写回本类型的结果供读取和推送
+            fs::write(&result_csv_path, content)?;
+        } else if !self.mode.reuse_result() {
+            // 预览模式复用已有测速结果时跳过下载与测速；共享测速配置按共用它的解析组所需的最大数量测速
+            let test_num = test_profile
+                .and_then(|profile| self.test_counts.get(profile))
+                .map(|&(v4_num, v6_num)| if ip_type == "IPv4" { v4_num } else { v6_num })
+                .unwrap_or(0)
+                .max(num);
+
+            // 获取IP地址写入 -f 文件
+            match output_file {
+                Some(file) if !sources.is_empty() => {
+                    self.fetch_and_filter_ips(sources, test_num, cidr_sample, ip_type, Some(file))?;
+                }
+                Some(file) if builtin_sample > 0 => {
+                    self.write_builtin_pool(ip_type, builtin_sample, file)?;
+                }
+                None if !sources.is_empty() => {
+                    crate::warning_println(format_args!(
+                        "测速参数中没有 {}，未使用设置的地址来源",
+                        self.tester.input_file_flag
+                    ));
+                }
+                None if builtin_sample > 0 => {
+                    crate::info_println(format_args!(
+                        "测速参数中没有 {}，不使用内置 Cloudflare IP 段",
+                        self.tester.input_file_flag
+                    ));
+                }
+                _ => {}
+            }
 
-        // 打印将要执行的命令
-        println!("[执行] {} {}\n", cfst_path, cf_command);
+            crate::print_section_header("运行测速程序");
 
-        // 执行测速
-        let mut cmd = Command::new(cfst_path);
-        cmd.args(cf_command.split_whitespace());
+            // 打印将要执行的命令
+            println!("[执行] {} {}\n", self.tester.path, cf_command);
 
-        if num > 0 {
-            let num_str = num.to_string();
-            cmd.arg("-dn").arg(&num_str);
-            cmd.arg("-p").arg(&num_str);
-        }
+            // 执行测速
+            let status = self.tester.command(cf_command, test_num)?.status()?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("{} 执行失败", self.tester.name));
+            }
 
-        let status = cmd.status()?;
-        if !status.success() {
-            return Err(anyhow::anyhow!("{} 执行失败", crate::CLOUDFLAREST_RUST));
+            if let Some(key) = test_key {
+                let content = fs::read_to_string(&result_csv_path).unwrap_or_default();
+                self.test_results.borrow_mut().insert(key, content);
+            }
         }
 
         // 读取测速结果
-        let result_csv_path = super::utils::get_result_csv_path(cf_command);
-        if std::path::Path::new(&result_csv_path).exists() {
-            let content = fs::read_to_string(&result_csv_path)?;
-
-            ips = content
-                .lines()
-                .skip(1) // 跳过标题行
-                .filter_map(|line| {
-                    let fields: Vec<&str> = line.split(',').collect();
-                    fields.first().map(ToString::to_string)
-                })
-                .filter(|ip| {
-                    let is_ipv4 = ip.contains('.');
-                    ip_type.is_empty()
-                        || (ip_type == "IPv4" && is_ipv4)
-                        || (ip_type == "IPv6" && !is_ipv4)
-                })
-                .take(if num == 0 { usize::MAX } else { num as usize })
-                .collect();
-        }
-
-        // 处理DNS记录
-        if add_ddns != "未指定" && !ips.is_empty() {
-            // 验证Cloudflare账号
-            super::cloudflare_api::CloudflareApi::validate_cloudflare_account(
-                self, x_email, api_key, zone_id,
-            )?;
+        let ips = self.read_ips_from_csv(ip_type, num, cf_command)?;
 
-            // 重启插件
+        // 处理DNS记录，同一次测速结果依次更新到每个解析目标
+        if !targets.is_empty() && !ips.is_empty() {
+            // 重启插件，预览模式不改动系统服务
             #[cfg(target_os = "linux")]
             {
-                if !clien.is_empty() && clien != "未指定" && plugin_status == Some("stopped") {
+                if !self.mode.is_plan()
+                    && !clien.is_empty()
+                    && clien != "未指定"
+                    && plugin_status == Some("stopped")
+                {
                     crate::print_section_header("插件重启");
                     crate::info_println(format_args!("正在重启插件 {}", clien));
                     let status = Command::new(format!("/etc/init.d/{}", clien))
@@ -275,131 +378,116 @@ impl IpOperations for super::start_struct::Start {
                 }
             }
 
-            // 删除旧记录
-            let mut records_to_delete = Vec::new();
-
-            // 收集所有需要删除的记录
-            for domain in domains {
-                if ip_type.is_empty() {
-                    // 当ip_type为空时，获取并删除所有类型的记录
-                    let existing_records =
-                        self.get_dns_records(x_email, api_key, zone_id, domain, None)?;
-                    let exclude_set: std::collections::HashSet<_> = ips.iter().cloned().collect();
-                    for record in &existing_records {
-                        if !exclude_set.contains(&record.content) {
-                            // 根据记录内容判断记录类型
-                            let record_type = if record.content.contains('.') {
-                                "A"
-                            } else {
-                                "AAAA"
-                            };
-                            records_to_delete.push((
-                                domain.to_string(),
-                                record.content.to_string(),
-                                record_type.to_string(),
-                                record.id.to_string(),
-                            ));
-                        }
+            // 某个解析目标出错时记为该目标失败，继续更新其余目标
+            for target in targets {
+                match self.update_target(target, &ips, record_type, record_settings, distribution) {
+                    Ok((map, failure)) => {
+                        domain_ip_map.extend(map);
+                        failures.push(failure);
                     }
-                } else {
-                    // 当ip_type不为空时，只获取并删除对应类型的记录
-                    let existing_records =
-                        self.get_dns_records(x_email, api_key, zone_id, domain, record_type)?;
-                    let exclude_set: std::collections::HashSet<_> = ips.iter().cloned().collect();
-                    for record in &existing_records {
-                        if !exclude_set.contains(&record.content) {
-                            records_to_delete.push((
-                                domain.to_string(),
-                                record.content.to_string(),
-                                record_type.unwrap().to_string(),
-                                record.id.to_string(),
-                            ));
-                        }
+                    Err(e) => {
+                        let account = target.account.account_name.clone();
+                        crate::error_println(format_args!("账户 {} 更新失败: {}", account, e));
+                        failures.push(Some(TargetFailure::Error {
+                            account,
+                            error: e.to_string(),
+                        }));
                     }
                 }
             }
+        }
 
-            // 统一的格式化函数，用于处理"→ -"和"→ +"的显示，实现自适应左对齐
-            fn format_dns_operation(
-                left: &str,
-                arrow: &str,
-                right: &str,
-                max_left_width: usize,
-            ) -> String {
-                format!(
-                    "{:<width$} {} {}",
-                    left,
-                    arrow,
-                    right,
-                    width = max_left_width
-                )
-            }
+        Ok((ips, domain_ip_map, failures))
+    }
 
-            // 创建域名和IP的映射关系
-            let domain_ip_mapping = super::utils::create_domain_ip_mapping(&ips, domains, add_ddns);
-
-            let max_domain_width = records_to_delete
-                .iter()
-                .map(|(d, _, _, _)| d.len())
-                .max()
-                .unwrap_or(0);
-
-            // 如果有需要删除的记录，则显示删除节点章节标题并执行删除
-            if !records_to_delete.is_empty() {
-                crate::print_section_header("删除节点");
-                crate::info_println(format_args!("开始删除 {} 个节点:", records_to_delete.len()));
-
-                let mut delete_success_count = 0;
-                for (domain, ip, _record_type, record_id) in records_to_delete {
-                    if self.delete_dns_record(x_email, api_key, zone_id, &record_id)? {
-                        // 在这里集中处理删除记录的格式化输出
-                        print!("  "); // 缩进
-                        let formatted_output =
-                            format_dns_operation(&domain, "→ -", &ip, max_domain_width);
-                        crate::success_println(format_args!("{}", formatted_output));
-                        delete_success_count += 1;
-                    }
-                }
+    fn update_target(
+        &self,
+        target: &DdnsTarget,
+        ips: &[String],
+        record_type: Option<&str>,
+        record_settings: &RecordSettings,
+        distribution: &Distribution,
+    ) -> Result<TargetOutcome> {
+        let account = target.account;
+        let domains = &target.domains;
+        let provider = provider_for(account, &self.request_budget);
+
+        // 验证账户凭据
+        provider.validate()?;
+
+        // 确定每个域名所属的区域
+        let mut zone_ids = std::collections::HashMap::new();
+        for domain in domains {
+            zone_ids.insert(
+                domain.to_string(),
+                self.get_zone_id(account, provider.as_ref(), domain)?,
+            );
+        }
 
-                crate::info_println(format_args!("总共删除了 {} 个节点", delete_success_count));
-            }
+        // 获取现有记录并与期望的映射对比，生成变更计划
+        let existing =
+            self.fetch_existing_records(provider.as_ref(), &zone_ids, domains, record_type)?;
+        let domain_ip_mapping = super::utils::create_domain_ip_mapping(ips, domains, distribution);
+        let plan = plan_reconciliation(&domain_ip_mapping, &existing, record_settings);
 
-            // 打印添加节点章节标题
-            crate::print_section_header("添加节点");
-            crate::info_println(format_args!("开始添加 {} 个节点:", domain_ip_mapping.len()));
-
-            let mut success_count = 0;
-            for (domain, ip) in domain_ip_mapping {
-                // 根据IP地址内容确定记录类型
-                let record_type = if ip.contains('.') { "A" } else { "AAAA" };
-                let res =
-                    self.create_dns_record(x_email, api_key, zone_id, &domain, record_type, &ip)?;
-                if res {
-                    // 在这里集中处理添加记录的格式化输出
-                    print!("  "); // 缩进
-                    let formatted_output =
-                        format_dns_operation(&domain, "→ +", &ip, max_domain_width);
-                    crate::success_println(format_args!("{}", formatted_output));
-                    success_count += 1;
-                    domain_ip_map
-                        .entry(domain)
-                        .or_insert_with(Vec::new)
-                        .push(ip);
-                }
+        if self.mode.is_plan() {
+            plan.print_preview();
+            return Ok((std::collections::HashMap::new(), None));
+        }
+
+        let outcome =
+            self.apply_plan_batch(provider.as_ref(), &zone_ids, &plan, record_settings)?;
+
+        // 缓存中对应类型的记录已过期，之后的解析组需要重新读取
+        if plan.has_changes() {
+            let zones: std::collections::HashSet<&String> = zone_ids.values().collect();
+            for zone_id in zones {
+                self.invalidate_zone_records(provider.as_ref(), zone_id, record_type);
             }
+        }
 
-            crate::info_println(format_args!("总共添加了 {} 个节点", success_count));
+        // 写入的新记录不足时恢复执行前的记录，没有任何修改时直接记为失败
+        let required = plan.required_writes(record_settings.min_records);
+        let written = outcome.written();
+        if written < required && !outcome.has_changes() {
+            let error = if outcome.errors.is_empty() {
+                format!("未写入新记录，少于要求的 {} 条", required)
+            } else {
+                outcome.errors.join("；")
+            };
+            return Ok((
+                std::collections::HashMap::new(),
+                Some(TargetFailure::Error {
+                    account: account.account_name.clone(),
+                    error,
+                }),
+            ));
+        }
+        if written < required {
+            crate::error_println(format_args!(
+                "仅写入 {} 条新记录，少于要求的 {} 条，开始回滚",
+                written, required
+            ));
+            let report = self.rollback(
+                provider.as_ref(),
+                &zone_ids,
+                &outcome,
+                record_settings,
+                &account.account_name,
+                required,
+            );
+            return Ok((
+                std::collections::HashMap::new(),
+                Some(TargetFailure::RolledBack(report)),
+            ));
         }
 
-        Ok((ips, domain_ip_map))
+        Ok((outcome.domain_ip_map, None))
     }
 
-    /// 获取 CloudflareST-Rust 可执行文件路径
-    fn get_cfst_path(&self) -> String {
-        #[cfg(target_os = "windows")]
-        return format!(".\\{}", crate::CLOUDFLAREST_RUST);
-
-        #[cfg(any(target_os = "linux", target_os = "macos"))]
-        return format!("./{}", crate::CLOUDFLAREST_RUST);
+    fn has_test_result(&self, test_profile: &str, ip_type: &str) -> bool {
+        self.test_results
+            .borrow()
+            .contains_key(&format!("{}/{}", test_profile, ip_type))
     }
 }
\ No newline at end of file
@@ -0,0 +1,650 @@
+use super::signing::sha256_hex;
+use crate::IpSource;
+use std::collections::HashSet;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// URL 来源的缓存目录
+const SOURCE_CACHE_DIR: &str = "cfrs-ip-cache";
+
+/// 地址来源中的一条有效记录：单个IP或网段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceEntry {
+    Ip(IpAddr),
+    Cidr(IpAddr, u8),
+}
+
+/// 一行地址来源内容的解析结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParsedLine {
+    /// 空行或注释
+    Empty,
+    Entry(SourceEntry),
+    /// 无法识别的内容，如 HTML 错误页面
+    Invalid,
+}
+
+/// 从地址来源中整理出的地址，以及被忽略的各类内容数量
+#[derive(Debug, Default)]
+pub struct SourceSummary {
+    pub entries: Vec<SourceEntry>,
+    pub invalid: usize,
+    pub reserved: usize,
+    pub duplicate: usize,
+}
+
+impl SourceEntry {
+    pub fn is_ipv4(&self) -> bool {
+        match self {
+            SourceEntry::Ip(ip) | SourceEntry::Cidr(ip, _) => ip.is_ipv4(),
+        }
+    }
+
+    /// 网段按网络地址判断，单个IP直接判断
+    pub fn is_reserved(&self) -> bool {
+        match self {
+            SourceEntry::Ip(ip) | SourceEntry::Cidr(ip, _) => is_reserved(*ip),
+        }
+    }
+
+    /// 按 /24（IPv4）或 /48（IPv6）划分网段，每段随机抽取指定数量的IP；不抽样时原样返回
+    fn expand(self, per_block: u32) -> Box<dyn Iterator<Item = SourceEntry>> {
+        let SourceEntry::Cidr(network, _) = self else {
+            return Box::new(std::iter::once(self));
+        };
+        if per_block == 0 {
+            return Box::new(std::iter::once(self));
+        }
+
+        let base = to_u128(network);
+        let (blocks, block_size) = self.blocks();
+        // 超过 2^32 个网段时只遍历前面的部分，实际数量由调用方截断
+        let blocks = blocks.min(1 << 32);
+
+        Box::new((0..blocks).flat_map(move |block| {
+            let start = base + block * block_size;
+            (0..per_block)
+                .map(move |_| SourceEntry::Ip(random_host(start, block_size, network.is_ipv4())))
+        }))
+    }
+
+    /// 按 /24（IPv6 为 /48）划分后的网段数量，以及每段的大小
+    fn blocks(&self) -> (u128, u128) {
+        let SourceEntry::Cidr(network, prefix) = *self else {
+            return (1, 1);
+        };
+        let (bits, block_prefix) = if network.is_ipv4() {
+            (32, 24)
+        } else {
+            (128, 48)
+        };
+        let block_prefix = block_prefix.max(prefix);
+        (
+            1u128 << (block_prefix - prefix),
+            1u128 << (bits - block_prefix),
+        )
+    }
+}
+
+/// 在一段地址中随机取一个，IPv4 跳过网络地址和广播地址
+fn random_host(start: u128, block_size: u128, ipv4: bool) -> IpAddr {
+    let host = if ipv4 && block_size > 2 {
+        1 + random_u128() % (block_size - 2)
+    } else {
+        random_u128() % block_size
+    };
+    from_u128(start + host, ipv4)
+}
+
+/// Cloudflare 公布的 IPv4 段（https://www.cloudflare.com/ips-v4）
+pub const CLOUDFLARE_IPV4: &[&str] = &[
+    "173.245.48.0/20",
+    "103.21.244.0/22",
+    "103.22.200.0/22",
+    "103.31.4.0/22",
+    "141.101.64.0/18",
+    "108.162.192.0/18",
+    "190.93.240.0/20",
+    "188.114.96.0/20",
+    "197.234.240.0/22",
+    "198.41.128.0/17",
+    "162.158.0.0/15",
+    "104.16.0.0/13",
+    "104.24.0.0/14",
+    "172.64.0.0/13",
+    "131.0.72.0/22",
+];
+
+/// Cloudflare 公布的 IPv6 段（https://www.cloudflare.com/ips-v6）
+pub const CLOUDFLARE_IPV6: &[&str] = &[
+    "2400:cb00::/32",
+    "2606:4700::/32",
+    "2803:f800::/32",
+    "2405:b500::/32",
+    "2405:8100::/32",
+    "2a06:98c0::/29",
+    "2c0f:f248::/32",
+];
+
+/// 指定类型的 Cloudflare IP 段，custom 中没有该类型的有效网段时使用内置网段
+pub fn cloudflare_ranges(custom: &[String], ipv4: bool) -> Vec<SourceEntry> {
+    let parse = |ranges: &mut dyn Iterator<Item = &str>| -> Vec<SourceEntry> {
+        ranges
+            .filter_map(|line| match parse_line(line) {
+                ParsedLine::Entry(entry) if entry.is_ipv4() == ipv4 => Some(entry),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let ranges = parse(&mut custom.iter().map(String::as_str));
+    if !ranges.is_empty() {
+        return ranges;
+    }
+    let builtin = if ipv4 {
+        CLOUDFLARE_IPV4
+    } else {
+        CLOUDFLARE_IPV6
+    };
+    parse(&mut builtin.iter().copied())
+}
+
+/// 从多个网段中随机抽取 count 个不重复的 /24（IPv6 为 /48），每段取一个随机IP
+///
+/// count 不小于网段总数时每段都取一个
+pub fn sample_ranges(ranges: &[SourceEntry], count: usize) -> Vec<SourceEntry> {
+    let blocks: Vec<(u128, u128)> = ranges.iter().map(SourceEntry::blocks).collect();
+    let total = blocks
+        .iter()
+        .fold(0u128, |sum, (count, _)| sum.saturating_add(*count));
+
+    let pick = |mut index: u128| -> SourceEntry {
+        for (range, (count, size)) in ranges.iter().zip(&blocks) {
+            if index < *count {
+                return match *range {
+                    SourceEntry::Cidr(network, _) => SourceEntry::Ip(random_host(
+                        to_u128(network) + index * size,
+                        *size,
+                        network.is_ipv4(),
+                    )),
+                    ip => ip,
+                };
+            }
+            index -= count;
+        }
+        unreachable!()
+    };
+
+    if total <= count as u128 {
+        return (0..total).map(pick).collect();
+    }
+
+    let mut chosen = HashSet::new();
+    let mut sampled = Vec::with_capacity(count);
+    while sampled.len() < count {
+        let index = random_u128() % total;
+        if chosen.insert(index) {
+            sampled.push(pick(index));
+        }
+    }
+    sampled
+}
+
+impl fmt::Display for SourceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceEntry::Ip(ip) => write!(f, "{}", ip),
+            SourceEntry::Cidr(ip, prefix) => write!(f, "{}/{}", ip, prefix),
+        }
+    }
+}
+
+/// 解析一行地址来源内容
+///
+/// 支持 `#`、`//` 注释和 `IP#备注`，`IP:端口`、`[IPv6]:端口`，以及空白或逗号后的附加字段
+pub fn parse_line(line: &str) -> ParsedLine {
+    let line = line.split('#').next().unwrap_or_default();
+    let line = line.split("//").next().unwrap_or_default();
+    let Some(token) = line
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .find(|s| !s.is_empty())
+    else {
+        return ParsedLine::Empty;
+    };
+
+    match parse_token(token) {
+        Some(entry) => ParsedLine::Entry(entry),
+        None => ParsedLine::Invalid,
+    }
+}
+
+fn parse_token(token: &str) -> Option<SourceEntry> {
+    // [IPv6]:端口
+    if let Some(rest) = token.strip_prefix('[') {
+        let (addr, port) = rest.split_once(']')?;
+        if !port.is_empty() && port.strip_prefix(':')?.parse::<u16>().is_err() {
+            return None;
+        }
+        return addr
+            .parse::<Ipv6Addr>()
+            .ok()
+            .map(|ip| SourceEntry::Ip(ip.into()));
+    }
+
+    if let Some((addr, prefix)) = token.split_once('/') {
+        let ip: IpAddr = addr.parse().ok()?;
+        let prefix: u8 = prefix.parse().ok()?;
+        let bits = if ip.is_ipv4() { 32 } else { 128 };
+        if prefix > bits {
+            return None;
+        }
+        if prefix == bits {
+            return Some(SourceEntry::Ip(ip));
+        }
+        // 统一为网络地址，便于去重
+        let mask = if prefix == 0 {
+            0
+        } else {
+            u128::MAX << (128 - prefix) >> (128 - bits)
+        };
+        let network = from_u128(to_u128(ip) & mask, ip.is_ipv4());
+        return Some(SourceEntry::Cidr(network, prefix));
+    }
+
+    if let Ok(ip) = token.parse::<IpAddr>() {
+        return Some(SourceEntry::Ip(ip));
+    }
+
+    // IPv4:端口
+    let (addr, port) = token.rsplit_once(':')?;
+    port.parse::<u16>().ok()?;
+    addr.parse::<Ipv4Addr>()
+        .ok()
+        .map(|ip| SourceEntry::Ip(ip.into()))
+}
+
+/// 私有、回环、链路本地等不能用于公网解析的地址
+pub fn is_reserved(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_reserved(v4.into()),
+            None => {
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+            }
+        },
+    }
+}
+
+/// 从地址来源内容中取出指定类型的地址，去重并过滤保留地址，最多取 max_count 个
+///
+/// cidr_sample 为 0 时网段原样保留，交给测速程序展开
+pub fn collect_entries(
+    text: &str,
+    ipv4: bool,
+    cidr_sample: u32,
+    max_count: usize,
+) -> SourceSummary {
+    let mut summary = SourceSummary::default();
+    let mut seen = HashSet::new();
+
+    for line in text.lines() {
+        if summary.entries.len() >= max_count {
+            break;
+        }
+        let entry = match parse_line(line) {
+            ParsedLine::Empty => continue,
+            ParsedLine::Invalid => {
+                summary.invalid += 1;
+                continue;
+            }
+            ParsedLine::Entry(entry) => entry,
+        };
+        if entry.is_ipv4() != ipv4 {
+            continue;
+        }
+        if entry.is_reserved() {
+            summary.reserved += 1;
+            continue;
+        }
+
+        for entry in entry.expand(cidr_sample) {
+            if summary.entries.len() >= max_count {
+                break;
+            }
+            // 抽样得到的地址可能落在网段内的保留范围
+            if entry.is_reserved() {
+                continue;
+            }
+            if seen.insert(entry) {
+                summary.entries.push(entry);
+            } else {
+                summary.duplicate += 1;
+            }
+        }
+    }
+
+    summary
+}
+
+/// 解析组的全部地址来源，v4_url 或 v6_url 排在最前
+pub fn source_list(url: &str, sources: &[IpSource]) -> Vec<IpSource> {
+    let mut list = Vec::new();
+    if !url.is_empty() {
+        list.push(IpSource::from_url(url));
+    }
+    list.extend_from_slice(sources);
+    list
+}
+
+/// 内容中是否有任意一条可识别的IP或网段，用于判断下载结果能否写入缓存
+pub fn has_entries(text: &str) -> bool {
+    text.lines()
+        .any(|line| matches!(parse_line(line), ParsedLine::Entry(_)))
+}
+
+/// 合并多个来源的地址：先按权重比例为各来源分配数量，不足的部分再依次由其余来源补足
+///
+/// 返回合并后的地址和跨来源重复的数量
+pub fn merge_entries(lists: &[(Vec<SourceEntry>, u32)], total: usize) -> (Vec<SourceEntry>, usize) {
+    let weight_sum: u64 = lists.iter().map(|(_, weight)| *weight as u64).sum();
+    let mut merged = Vec::new();
+    let mut seen = HashSet::new();
+    let mut duplicate = 0;
+    let mut cursors = vec![0; lists.len()];
+
+    let mut take = |index: usize, quota: usize, merged: &mut Vec<SourceEntry>| {
+        let entries = &lists[index].0;
+        let mut taken = 0;
+        while taken < quota && merged.len() < total && cursors[index] < entries.len() {
+            let entry = entries[cursors[index]];
+            cursors[index] += 1;
+            if seen.insert(entry) {
+                merged.push(entry);
+                taken += 1;
+            } else {
+                duplicate += 1;
+            }
+        }
+    };
+
+    for (index, (_, weight)) in lists.iter().enumerate() {
+        // 权重都为 0 时不按比例分配，直接依次补足
+        let quota = (total as u64 * *weight as u64)
+            .checked_div(weight_sum)
+            .unwrap_or(0);
+        take(index, quota as usize, &mut merged);
+    }
+    for index in 0..lists.len() {
+        take(index, usize::MAX, &mut merged);
+    }
+
+    (merged, duplicate)
+}
+
+/// 本地缓存的来源内容路径，获取失败时使用上次成功的内容
+pub fn cache_path(url: &str) -> PathBuf {
+    Path::new(SOURCE_CACHE_DIR).join(format!("{}.txt", &sha256_hex(url)[..16]))
+}
+
+fn to_u128(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(ip) => u32::from(ip) as u128,
+        IpAddr::V6(ip) => u128::from(ip),
+    }
+}
+
+fn from_u128(value: u128, ipv4: bool) -> IpAddr {
+    if ipv4 {
+        IpAddr::V4(Ipv4Addr::from(value as u32))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(value))
+    }
+}
+
+/// 抽样用的 xorshift 随机数，不需要密码学强度
+fn random_u128() -> u128 {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+    let next = || {
+        let mut x = STATE.load(Ordering::Relaxed);
+        if x == 0 {
+            x = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0)
+                | 1;
+        }
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        STATE.store(x, Ordering::Relaxed);
+        x
+    };
+    ((next() as u128) << 64) | next() as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> SourceEntry {
+        SourceEntry::Ip(s.parse().unwrap())
+    }
+
+    fn cidr(s: &str, prefix: u8) -> SourceEntry {
+        SourceEntry::Cidr(s.parse().unwrap(), prefix)
+    }
+
+    #[test]
+    fn parses_bare_ip() {
+        assert_eq!(
+            parse_line("104.16.0.1"),
+            ParsedLine::Entry(ip("104.16.0.1"))
+        );
+        assert_eq!(
+            parse_line("104.16.0.1:443"),
+            ParsedLine::Entry(ip("104.16.0.1"))
+        );
+        assert_eq!(
+            parse_line("2606:4700::1"),
+            ParsedLine::Entry(ip("2606:4700::1"))
+        );
+        assert_eq!(
+            parse_line("[2606:4700::1]:443"),
+            ParsedLine::Entry(ip("2606:4700::1"))
+        );
+    }
+
+    #[test]
+    fn parses_cidr() {
+        assert_eq!(
+            parse_line("104.16.0.0/13"),
+            ParsedLine::Entry(cidr("104.16.0.0", 13))
+        );
+        assert_eq!(
+            parse_line("2606:4700::/32"),
+            ParsedLine::Entry(cidr("2606:4700::", 32))
+        );
+        // 主机位长度的网段就是单个IP
+        assert_eq!(
+            parse_line("104.16.0.1/32"),
+            ParsedLine::Entry(ip("104.16.0.1"))
+        );
+    }
+
+    #[test]
+    fn clears_host_bits_of_cidr() {
+        assert_eq!(
+            parse_line("104.16.5.7/24"),
+            ParsedLine::Entry(cidr("104.16.5.0", 24))
+        );
+        assert_eq!(
+            parse_line("2606:4700:1::1/32"),
+            ParsedLine::Entry(cidr("2606:4700::", 32))
+        );
+        assert_eq!(
+            parse_line("1.2.3.4/0"),
+            ParsedLine::Entry(cidr("0.0.0.0", 0))
+        );
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        for line in ["", "   ", "# 注释", "// 注释", "\t# 缩进的注释"] {
+            assert_eq!(parse_line(line), ParsedLine::Empty, "{:?}", line);
+        }
+        for line in [
+            "104.16.0.1 # 备注",
+            "104.16.0.1#备注",
+            "104.16.0.1 // 备注",
+            "104.16.0.1,443,HKG",
+            "  104.16.0.1\t100ms",
+        ] {
+            assert_eq!(
+                parse_line(line),
+                ParsedLine::Entry(ip("104.16.0.1")),
+                "{:?}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        for line in [
+            "<html>",
+            "not-an-ip",
+            "300.1.1.1",
+            "104.16.0.0/33",
+            "2606:4700::/129",
+            "104.16.0.0/abc",
+            "104.16.0.1:99999",
+            "[2606:4700::1]:abc",
+            "[104.16.0.1]",
+        ] {
+            assert_eq!(parse_line(line), ParsedLine::Invalid, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn collects_one_family_from_mixed_input() {
+        let text = "\
+# 混合来源
+104.16.0.1
+2606:4700::1
+104.16.0.1:443
+10.0.0.1
+192.168.0.0/16
+<html>
+
+104.17.0.0/16
+2606:4700::/32
+fe80::1
+";
+        let v4 = collect_entries(text, true, 0, usize::MAX);
+        assert_eq!(v4.entries, [ip("104.16.0.1"), cidr("104.17.0.0", 16)]);
+        assert_eq!((v4.invalid, v4.reserved, v4.duplicate), (1, 2, 1));
+
+        let v6 = collect_entries(text, false, 0, usize::MAX);
+        assert_eq!(v6.entries, [ip("2606:4700::1"), cidr("2606:4700::", 32)]);
+        assert_eq!((v6.invalid, v6.reserved, v6.duplicate), (1, 1, 0));
+    }
+
+    #[test]
+    fn samples_each_block_of_cidr() {
+        let summary = collect_entries("104.16.0.0/23", true, 2, usize::MAX);
+        assert_eq!(summary.entries.len(), 4);
+        let block = |entry: &SourceEntry| match entry {
+            SourceEntry::Ip(IpAddr::V4(v4)) => v4.octets()[2],
+            other => panic!("应抽样为单个IP: {}", other),
+        };
+        let blocks: Vec<u8> = summary.entries.iter().map(block).collect();
+        assert_eq!(blocks.iter().filter(|b| **b == 0).count(), 2);
+        assert_eq!(blocks.iter().filter(|b| **b == 1).count(), 2);
+    }
+
+    #[test]
+    fn stops_at_max_count() {
+        let text = "104.16.0.1\n104.16.0.2\n104.16.0.3\n";
+        let summary = collect_entries(text, true, 0, 2);
+        assert_eq!(summary.entries, [ip("104.16.0.1"), ip("104.16.0.2")]);
+
+        let summary = collect_entries("104.16.0.0/16", true, 1, 5);
+        assert_eq!(summary.entries.len(), 5);
+    }
+
+    #[test]
+    fn merges_overlapping_sources_by_weight() {
+        let a = vec![ip("104.16.0.1"), ip("104.16.0.2"), ip("104.16.0.3")];
+        let b = vec![ip("104.16.0.2"), ip("104.16.0.3"), ip("104.16.0.4")];
+        let (merged, duplicate) = merge_entries(&[(a, 1), (b, 1)], 4);
+        assert_eq!(
+            merged,
+            [
+                ip("104.16.0.1"),
+                ip("104.16.0.2"),
+                ip("104.16.0.3"),
+                ip("104.16.0.4")
+            ]
+        );
+        assert_eq!(duplicate, 1);
+    }
+
+    #[test]
+    fn fills_short_source_from_others() {
+        let a = vec![ip("104.16.0.1")];
+        let b = (2..=5).map(|n| ip(&format!("104.16.0.{}", n))).collect();
+        let (merged, duplicate) = merge_entries(&[(a, 3), (b, 1)], 4);
+        assert_eq!(
+            merged,
+            [
+                ip("104.16.0.1"),
+                ip("104.16.0.2"),
+                ip("104.16.0.3"),
+                ip("104.16.0.4")
+            ]
+        );
+        assert_eq!(duplicate, 0);
+
+        // 权重都为 0 时依次补足
+        let a = vec![ip("104.16.0.1"), ip("104.16.0.2")];
+        let b = vec![ip("104.16.0.3")];
+        let (merged, _) = merge_entries(&[(a, 0), (b, 0)], 3);
+        assert_eq!(
+            merged,
+            [ip("104.16.0.1"), ip("104.16.0.2"), ip("104.16.0.3")]
+        );
+    }
+
+    #[test]
+    fn merges_only_the_requested_family() {
+        let first = "104.16.0.1\n2606:4700::1\n104.17.0.0/16\n";
+        let second = "2606:4700::2\n104.16.0.1\n104.16.0.9\n";
+        let lists: Vec<(Vec<SourceEntry>, u32)> = [first, second]
+            .iter()
+            .map(|text| (collect_entries(text, true, 0, usize::MAX).entries, 1))
+            .collect();
+
+        let (merged, duplicate) = merge_entries(&lists, 10);
+        assert_eq!(
+            merged,
+            [ip("104.16.0.1"), cidr("104.17.0.0", 16), ip("104.16.0.9")]
+        );
+        assert_eq!(duplicate, 1);
+        assert!(merged.iter().all(SourceEntry::is_ipv4));
+    }
+}
\ No newline at end of file
@@ -1,9 +1,19 @@
+pub mod alidns;
 pub mod cloudflare_api;
 pub mod ddns_operations;
 pub mod dns_operations;
+pub mod dnspod;
 pub mod ip_operations;
+pub mod ip_source;
+pub mod reconcile;
+pub mod rfc2136;
+pub mod signing;
+pub mod simulate;
 pub mod start_struct;
+pub mod test_result;
+pub mod tester;
 pub mod utils;
+pub mod zone_operations;
 
 // 重新导出主要类型和函数
 pub use start_struct::Start;
\ No newline at end of file
@@ -0,0 +1,1098 @@
+use super::dns_operations::{
+    BatchUnsupported, DnsProvider, DnsRecord, RecordSettings, display_name, same_name,
+};
+use super::zone_operations::ZoneOperations;
+use anyhow::Result;
+use console::measure_text_width;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// 逐条提交时同时进行的最大请求数
+const MAX_CONCURRENT_WRITES: usize = 4;
+
+/// 一次解析的DNS变更计划
+#[derive(Debug, Default)]
+pub struct ReconcilePlan {
+    /// 内容已符合预期、保持不变的记录：(域名, 记录)
+    pub keep: Vec<(String, DnsRecord)>,
+    /// 原地更新的记录：(域名, 原记录, 新IP)
+    pub update: Vec<(String, DnsRecord, String)>,
+    /// 需要新建的记录：(域名, IP)
+    pub create: Vec<(String, String)>,
+    /// 需要删除的记录：(域名, 记录)
+    pub delete: Vec<(String, DnsRecord)>,
+    /// 非 CFRS 创建、不会被改动的记录：(域名, 记录)
+    pub unmanaged: Vec<(String, DnsRecord)>,
+}
+
+/// 变更计划的实际执行结果，同时作为回滚所需的快照
+#[derive(Debug, Default)]
+pub struct ApplyOutcome {
+    /// 每个域名最终解析到的IP
+    pub domain_ip_map: HashMap<String, Vec<String>>,
+    /// 已原地更新的记录：(域名, 更新前的记录)
+    pub updated: Vec<(String, DnsRecord)>,
+    /// 已新建的记录：(域名, 记录ID, IP)
+    pub created: Vec<(String, String, String)>,
+    /// 已删除的记录：(域名, 删除前的记录)
+    pub deleted: Vec<(String, DnsRecord)>,
+    /// 执行过程中出错的操作
+    pub errors: Vec<String>,
+}
+
+impl ApplyOutcome {
+    /// 写入了新IP的记录数（新建与原地更新）
+    pub fn written(&self) -> usize {
+        self.updated.len() + self.created.len()
+    }
+
+    /// 是否对DNS做了需要回滚的修改
+    pub fn has_changes(&self) -> bool {
+        self.written() > 0 || !self.deleted.is_empty()
+    }
+
+    fn merge(&mut self, other: ApplyOutcome) {
+        for (domain, ips) in other.domain_ip_map {
+            self.domain_ip_map.entry(domain).or_default().extend(ips);
+        }
+        self.updated.extend(other.updated);
+        self.created.extend(other.created);
+        self.deleted.extend(other.deleted);
+        self.errors.extend(other.errors);
+    }
+}
+
+/// 回滚结果，用于输出与推送
+#[derive(Debug)]
+pub struct RollbackReport {
+    /// 回滚的解析目标所属账户
+    pub account: String,
+    /// 要求的最少新记录数
+    pub required: usize,
+    /// 实际写入的新记录数
+    pub written: usize,
+    /// 已恢复的旧记录数
+    pub restored: usize,
+    /// 已撤销的新记录数
+    pub removed: usize,
+    /// 回滚时出错的操作
+    pub errors: Vec<String>,
+}
+
+impl RollbackReport {
+    /// 生成推送消息
+    pub fn message(&self, ddns_name: &str, ip_type: &str) -> String {
+        let mut message = format!(
+            "CFRS 解析组 {} 在账户 {} 的 {} 更新失败：仅写入 {} 条新记录，少于要求的 {} 条，已回滚（恢复 {} 条旧记录，撤销 {} 条新记录）",
+            ddns_name,
+            self.account,
+            ip_type,
+            self.written,
+            self.required,
+            self.restored,
+            self.removed
+        );
+        if !self.errors.is_empty() {
+            message.push_str(&format!(
+                "，其中 {} 项回滚失败：{}",
+                self.errors.len(),
+                self.errors.join("；")
+            ));
+        }
+        message
+    }
+}
+
+/// 解析目标更新失败的原因，用于输出与推送
+#[derive(Debug)]
+pub enum TargetFailure {
+    /// 写入的新记录不足，已回滚
+    RolledBack(RollbackReport),
+    /// 更新过程中出错，其余解析目标照常更新
+    Error { account: String, error: String },
+}
+
+impl TargetFailure {
+    /// 生成推送消息
+    pub fn message(&self, ddns_name: &str, ip_type: &str) -> String {
+        match self {
+            TargetFailure::RolledBack(report) => report.message(ddns_name, ip_type),
+            TargetFailure::Error { account, error } => format!(
+                "CFRS 解析组 {} 在账户 {} 的 {} 更新失败：{}",
+                ddns_name, account, ip_type, error
+            ),
+        }
+    }
+}
+
+impl ReconcilePlan {
+    /// 是否需要对DNS做任何修改
+    pub fn has_changes(&self) -> bool {
+        !self.update.is_empty() || !self.create.is_empty() || !self.delete.is_empty()
+    }
+
+    /// 计划需要达到的最少新记录数，不超过计划本身要写入的数量
+    pub fn required_writes(&self, min_records: u32) -> usize {
+        (min_records as usize).min(self.update.len() + self.create.len())
+    }
+
+    /// 计划中最长的域名宽度，用于输出对齐
+    pub fn max_domain_width(&self) -> usize {
+        let width = |d: &String| measure_text_width(&display_name(d));
+        let keep = self.keep.iter().map(|(d, _)| width(d));
+        let update = self.update.iter().map(|(d, _, _)| width(d));
+        let create = self.create.iter().map(|(d, _)| width(d));
+        let delete = self.delete.iter().map(|(d, _)| width(d));
+        let unmanaged = self.unmanaged.iter().map(|(d, _)| width(d));
+        keep.chain(update)
+            .chain(create)
+            .chain(delete)
+            .chain(unmanaged)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 按区域拆分计划，批量接口以区域为单位提交
+    pub fn split_by_zone(
+        &self,
+        zone_ids: &HashMap<String, String>,
+    ) -> Vec<(String, ReconcilePlan)> {
+        let mut zones: Vec<(String, ReconcilePlan)> = Vec::new();
+        for (domain, record) in &self.keep {
+            zone_plan_mut(&mut zones, &zone_ids[domain])
+                .keep
+                .push((domain.clone(), record.clone()));
+        }
+        for (domain, record, ip) in &self.update {
+            zone_plan_mut(&mut zones, &zone_ids[domain]).update.push((
+                domain.clone(),
+                record.clone(),
+                ip.clone(),
+            ));
+        }
+        for (domain, ip) in &self.create {
+            zone_plan_mut(&mut zones, &zone_ids[domain])
+                .create
+                .push((domain.clone(), ip.clone()));
+        }
+        for (domain, record) in &self.delete {
+            zone_plan_mut(&mut zones, &zone_ids[domain])
+                .delete
+                .push((domain.clone(), record.clone()));
+        }
+        for (domain, record) in &self.unmanaged {
+            zone_plan_mut(&mut zones, &zone_ids[domain])
+                .unmanaged
+                .push((domain.clone(), record.clone()));
+        }
+
+        zones
+    }
+
+    /// 计划全部生效后每个域名解析到的IP
+    fn resolved_ips(&self) -> HashMap<String, Vec<String>> {
+        let mut domain_ip_map: HashMap<String, Vec<String>> = HashMap::new();
+        let keep = self.keep.iter().map(|(d, r)| (d, &r.content));
+        let update = self.update.iter().map(|(d, _, ip)| (d, ip));
+        let create = self.create.iter().map(|(d, ip)| (d, ip));
+        for (domain, ip) in keep.chain(update).chain(create) {
+            domain_ip_map
+                .entry(domain.clone())
+                .or_default()
+                .push(ip.clone());
+        }
+        domain_ip_map
+    }
+
+    /// 输出批量提交成功后的变更明细
+    fn print_batch_result(&self, width: usize) {
+        if !self.keep.is_empty() {
+            crate::info_println(format_args!("保留 {} 个未变化的节点", self.keep.len()));
+        }
+        if !self.has_changes() {
+            return;
+        }
+
+        crate::print_section_header("批量提交");
+        for (domain, record, ip) in &self.update {
+            print!("  "); // 缩进
+            let change = describe_update(record, ip);
+            let formatted_output =
+                format_dns_operation(&display_name(domain), "→ ~", &change, width);
+            crate::success_println(format_args!("{}", formatted_output));
+        }
+        for (domain, ip) in &self.create {
+            print!("  "); // 缩进
+            let formatted_output = format_dns_operation(&display_name(domain), "→ +", ip, width);
+            crate::success_println(format_args!("{}", formatted_output));
+        }
+        for (domain, record) in &self.delete {
+            print!("  "); // 缩进
+            let formatted_output =
+                format_dns_operation(&display_name(domain), "→ -", &record.content, width);
+            crate::success_println(format_args!("{}", formatted_output));
+        }
+        crate::info_println(format_args!(
+            "总共更新 {} / 添加 {} / 删除 {} 个节点",
+            self.update.len(),
+            self.create.len(),
+            self.delete.len()
+        ));
+    }
+
+    /// 批量提交成功后的执行结果，`created_ids` 为按顺序返回的新记录ID
+    fn batch_outcome(&self, created_ids: Vec<String>) -> ApplyOutcome {
+        let created = self
+            .create
+            .iter()
+            .zip(created_ids)
+            .map(|((domain, ip), id)| (domain.clone(), id, ip.clone()))
+            .collect();
+        let updated = self
+            .update
+            .iter()
+            .map(|(domain, record, _)| (domain.clone(), record.clone()))
+            .collect();
+
+        ApplyOutcome {
+            domain_ip_map: self.resolved_ips(),
+            updated,
+            created,
+            deleted: self.delete.clone(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// 批量提交失败时区域内记录保持原样，域名仍解析到原有的IP
+    fn failed_outcome(&self) -> ApplyOutcome {
+        let mut outcome = ApplyOutcome::default();
+        let updated = self.update.iter().map(|(d, r, _)| (d, r));
+        let unchanged = self.keep.iter().chain(&self.delete).map(|(d, r)| (d, r));
+        for (domain, record) in unchanged.chain(updated) {
+            outcome
+                .domain_ip_map
+                .entry(domain.clone())
+                .or_default()
+                .push(record.content.clone());
+        }
+        outcome
+    }
+
+    /// 同时有 CFRS 记录（含将要新建的）与非托管记录的域名
+    fn mixed_domains(&self) -> Vec<String> {
+        let managed: HashSet<&String> = self
+            .keep
+            .iter()
+            .filter(|(_, r)| r.is_managed())
+            .map(|(d, _)| d)
+            .chain(self.update.iter().map(|(d, _, _)| d))
+            .chain(self.create.iter().map(|(d, _)| d))
+            .chain(self.delete.iter().map(|(d, _)| d))
+            .collect();
+
+        let mut domains: Vec<String> = Vec::new();
+        for (domain, _) in &self.unmanaged {
+            let name = display_name(domain);
+            if managed.contains(domain) && !domains.contains(&name) {
+                domains.push(name);
+            }
+        }
+        domains
+    }
+
+    /// 提示同一域名下 CFRS 记录与非托管记录混在一起，多为升级前创建的记录
+    fn print_mixed_warning(&self) {
+        let domains = self.mixed_domains();
+        if domains.is_empty() {
+            return;
+        }
+        crate::warning_println(format_args!(
+            "{} 下同时有 CFRS 记录和非托管记录，解析会同时指向两者；如非托管记录是旧版本 CFRS 创建的，请在解析组中开启 adopt_records 接管",
+            domains.join("、")
+        ));
+    }
+
+    /// 输出不会被改动的非托管记录
+    fn print_unmanaged(&self, width: usize) {
+        if self.unmanaged.is_empty() {
+            return;
+        }
+        crate::print_section_header("非托管节点");
+        crate::warning_println(format_args!(
+            "以下 {} 个节点不是由 CFRS 创建，已跳过:",
+            self.unmanaged.len()
+        ));
+        for (domain, record) in &self.unmanaged {
+            print!("  "); // 缩进
+            let formatted_output =
+                format_dns_operation(&display_name(domain), "→ !", &record.content, width);
+            crate::warning_println(format_args!("{}", formatted_output));
+        }
+        self.print_mixed_warning();
+    }
+
+    /// 按域名和记录类型输出变更预览，不做任何修改
+    pub fn print_preview(&self) {
+        crate::print_section_header("变更预览");
+
+        let mut rows: Vec<(String, &str, String)> = Vec::new();
+        for (domain, record) in &self.keep {
+            let left = format!("{} ({})", display_name(domain), record.record_type);
+            rows.push((left, "→ =", record.content.clone()));
+        }
+        for (domain, record, ip) in &self.update {
+            let left = format!("{} ({})", display_name(domain), record_type_of(ip));
+            rows.push((left, "→ ~", describe_update(record, ip)));
+        }
+        for (domain, ip) in &self.create {
+            let left = format!("{} ({})", display_name(domain), record_type_of(ip));
+            rows.push((left, "→ +", ip.clone()));
+        }
+        for (domain, record) in &self.delete {
+            let left = format!("{} ({})", display_name(domain), record.record_type);
+            rows.push((left, "→ -", record.content.clone()));
+        }
+        for (domain, record) in &self.unmanaged {
+            let left = format!("{} ({})", display_name(domain), record.record_type);
+            rows.push((
+                left,
+                "→ !",
+                format!("{}（非CFRS记录，保留）", record.content),
+            ));
+        }
+        // 稳定排序，同一域名和类型下依次为保留、更新、添加、删除、未托管
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let width = rows
+            .iter()
+            .map(|(left, _, _)| measure_text_width(left))
+            .max()
+            .unwrap_or(0);
+        for (left, arrow, right) in &rows {
+            print!("  "); // 缩进
+            crate::info_println(format_args!(
+                "{}",
+                format_dns_operation(left, arrow, right, width)
+            ));
+        }
+
+        crate::info_println(format_args!(
+            "共计 保留 {} / 更新 {} / 添加 {} / 删除 {} / 非托管 {}",
+            self.keep.len(),
+            self.update.len(),
+            self.create.len(),
+            self.delete.len(),
+            self.unmanaged.len()
+        ));
+        self.print_mixed_warning();
+    }
+}
+
+/// 取出指定区域的子计划，不存在时新建
+fn zone_plan_mut<'a>(
+    zones: &'a mut Vec<(String, ReconcilePlan)>,
+    zone_id: &str,
+) -> &'a mut ReconcilePlan {
+    let index = match zones.iter().position(|(z, _)| z == zone_id) {
+        Some(index) => index,
+        None => {
+            zones.push((zone_id.to_string(), ReconcilePlan::default()));
+            zones.len() - 1
+        }
+    };
+    &mut zones[index].1
+}
+
+/// 以有限的并发数对每一项执行操作，结果按原顺序返回
+fn run_bounded<T: Sync, R: Send>(items: &[T], op: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..MAX_CONCURRENT_WRITES.min(items.len()) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = op(item);
+                    results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|result| result.expect("每一项都已执行"))
+        .collect()
+}
+
+/// 更新的描述，内容不变时说明是接管记录或只更新了代理与 TTL
+fn describe_update(record: &DnsRecord, ip: &str) -> String {
+    if record.content == ip && !record.is_managed() {
+        format!("{}（接管）", ip)
+    } else if record.content == ip {
+        format!("{}（更新代理/TTL）", ip)
+    } else {
+        format!("{} → {}", record.content, ip)
+    }
+}
+
+/// 输出单条记录的操作错误，返回记入结果的错误信息
+fn item_error(domain: &str, arrow: &str, error: &anyhow::Error, width: usize) -> String {
+    print!("  "); // 缩进
+    let formatted_output =
+        format_dns_operation(&display_name(domain), arrow, &error.to_string(), width);
+    crate::error_println(format_args!("{}", formatted_output));
+    format!("{} {}", display_name(domain), error)
+}
+
+/// 根据IP内容判断记录类型
+pub fn record_type_of(ip: &str) -> &'static str {
+    if ip.contains('.') { "A" } else { "AAAA" }
+}
+
+/// 统一的格式化函数，用于处理"→ -"、"→ +"和"→ ~"的显示，实现自适应左对齐
+pub fn format_dns_operation(left: &str, arrow: &str, right: &str, max_left_width: usize) -> String {
+    // 按显示宽度补齐，中文等宽字符占两列
+    let padding = max_left_width.saturating_sub(measure_text_width(left));
+    format!("{}{} {} {}", left, " ".repeat(padding), arrow, right)
+}
+
+/// 对比期望的 (域名, IP) 映射与现有记录，生成变更计划
+///
+/// 同一域名、同一记录类型内：内容已一致的记录保留，其余旧记录优先原地更新为
+/// 新IP以复用记录ID，多出的新IP新建，多出的旧记录删除。只有带管理标记的记录
+/// 会被更新或删除，其余记录原样保留并单独列出；开启接管时所有记录都视为托管，
+/// 没有标记的记录原地更新以加上标记。内容一致但代理或 TTL 与配置不同的托管
+/// 记录原地更新。
+pub fn plan_reconciliation(
+    desired: &[(String, String)],
+    existing: &[(String, Vec<DnsRecord>)],
+    settings: &RecordSettings,
+) -> ReconcilePlan {
+    let mut plan = ReconcilePlan::default();
+    let managed = |record: &DnsRecord| settings.adopt || record.is_managed();
+
+    for (domain, records) in existing {
+        for record_type in ["A", "AAAA"] {
+            let wanted: Vec<&str> = desired
+                .iter()
+                .filter(|(d, ip)| d == domain && record_type_of(ip) == record_type)
+                .map(|(_, ip)| ip.as_str())
+                .collect();
+            let mut current: Vec<&DnsRecord> = records
+                .iter()
+                .filter(|r| r.record_type == record_type)
+                .filter(|r| r.name.is_empty() || same_name(&r.name, domain))
+                .collect();
+            // 最久未修改的记录优先复用，较新的多余记录被删除
+            current.sort_by(|a, b| a.modified_on.cmp(&b.modified_on));
+
+            // 保留内容一致的记录（重复内容只保留一条），非托管记录不做改动
+            let mut kept = HashSet::new();
+            let mut stale = Vec::new();
+            for record in current {
+                if wanted.contains(&record.content.as_str()) && kept.insert(record.content.as_str())
+                {
+                    if !managed(record) || (record.is_managed() && record.matches(settings)) {
+                        plan.keep.push((domain.clone(), record.clone()));
+                    } else {
+                        plan.update
+                            .push((domain.clone(), record.clone(), record.content.clone()));
+                    }
+                } else if managed(record) {
+                    stale.push(record);
+                } else {
+                    plan.unmanaged.push((domain.clone(), record.clone()));
+                }
+            }
+
+            let mut missing = Vec::new();
+            for ip in wanted {
+                if !kept.contains(ip) && !missing.contains(&ip) {
+                    missing.push(ip);
+                }
+            }
+
+            // 旧记录与新IP两两配对做原地更新，剩余部分新建或删除
+            let mut stale = stale.into_iter();
+            for ip in missing {
+                match stale.next() {
+                    Some(record) => {
+                        plan.update
+                            .push((domain.clone(), record.clone(), ip.to_string()));
+                    }
+                    None => plan.create.push((domain.clone(), ip.to_string())),
+                }
+            }
+            for record in stale {
+                plan.delete.push((domain.clone(), record.clone()));
+            }
+        }
+    }
+
+    plan
+}
+
+pub trait ReconcileOperations {
+    /// 获取各域名现有的DNS记录，服务商支持时按区域整体读取并缓存
+    fn fetch_existing_records(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_ids: &HashMap<String, String>,
+        domains: &[String],
+        record_type: Option<&str>,
+    ) -> Result<Vec<(String, Vec<DnsRecord>)>>;
+
+    /// 逐条执行变更计划
+    fn apply_plan(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_ids: &HashMap<String, String>,
+        plan: &ReconcilePlan,
+        settings: &RecordSettings,
+    ) -> Result<ApplyOutcome>;
+
+    /// 按区域通过批量接口原子地执行变更计划，批量接口被拒绝时退回逐条执行
+    fn apply_plan_batch(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_ids: &HashMap<String, String>,
+        plan: &ReconcilePlan,
+        settings: &RecordSettings,
+    ) -> Result<ApplyOutcome>;
+
+    /// 撤销已执行的变更，恢复执行前的记录，单项出错时继续回滚其余记录
+    fn rollback(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_ids: &HashMap<String, String>,
+        outcome: &ApplyOutcome,
+        settings: &RecordSettings,
+        account: &str,
+        required: usize,
+    ) -> RollbackReport;
+}
+
+impl ReconcileOperations for super::start_struct::Start {
+    fn fetch_existing_records(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_ids: &HashMap<String, String>,
+        domains: &[String],
+        record_type: Option<&str>,
+    ) -> Result<Vec<(String, Vec<DnsRecord>)>> {
+        let mut existing = Vec::new();
+
+        if !provider.supports_zone_listing() {
+            for domain in domains {
+                let records = provider.get_dns_records(&zone_ids[domain], domain, record_type)?;
+                existing.push((domain.to_string(), records));
+            }
+            return Ok(existing);
+        }
+
+        // 每个区域只读取一次，再按域名在本地筛选
+        let mut zones: HashMap<&str, Vec<DnsRecord>> = HashMap::new();
+        for domain in domains {
+            let zone_id = zone_ids[domain].as_str();
+            if !zones.contains_key(zone_id) {
+                zones.insert(zone_id, self.zone_records(provider, zone_id, record_type)?);
+            }
+            let records = zones[zone_id]
+                .iter()
+                .filter(|r| same_name(&r.name, domain))
+                .cloned()
+                .collect();
+            existing.push((domain.to_string(), records));
+        }
+        Ok(existing)
+    }
+
+    fn apply_plan(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_ids: &HashMap<String, String>,
+        plan: &ReconcilePlan,
+        settings: &RecordSettings,
+    ) -> Result<ApplyOutcome> {
+        let mut outcome = ApplyOutcome::default();
+        let width = plan.max_domain_width();
+
+        for (domain, record) in &plan.keep {
+            outcome
+                .domain_ip_map
+                .entry(domain.clone())
+                .or_default()
+                .push(record.content.clone());
+        }
+
+        if !plan.keep.is_empty() {
+            crate::info_println(format_args!("保留 {} 个未变化的节点", plan.keep.len()));
+        }
+
+        plan.print_unmanaged(width);
+
+        // 先更新和新建，最后删除，尽量缩短域名无记录的时间
+        if !plan.update.is_empty() {
+            crate::print_section_header("更新节点");
+            crate::info_println(format_args!("开始更新 {} 个节点:", plan.update.len()));
+
+            let results = run_bounded(&plan.update, |(domain, record, ip)| {
+                provider.update_dns_record(
+                    &zone_ids[domain],
+                    &record.id,
+                    domain,
+                    record_type_of(ip),
+                    ip,
+                    settings,
+                )
+            });
+
+            // 单条出错时记为失败，继续处理其余记录
+            let mut success_count = 0;
+            for ((domain, record, ip), result) in plan.update.iter().zip(results) {
+                let updated = result.unwrap_or_else(|e| {
+                    outcome.errors.push(item_error(domain, "→ ~", &e, width));
+                    false
+                });
+                if updated {
+                    print!("  "); // 缩进
+                    let change = describe_update(record, ip);
+                    let formatted_output =
+                        format_dns_operation(&display_name(domain), "→ ~", &change, width);
+                    crate::success_println(format_args!("{}", formatted_output));
+                    success_count += 1;
+                    outcome
+                        .domain_ip_map
+                        .entry(domain.clone())
+                        .or_default()
+                        .push(ip.clone());
+                    outcome.updated.push((domain.clone(), record.clone()));
+                }
+            }
+
+            crate::info_println(format_args!("总共更新了 {} 个节点", success_count));
+        }
+
+        if !plan.create.is_empty() {
+            crate::print_section_header("添加节点");
+            crate::info_println(format_args!("开始添加 {} 个节点:", plan.create.len()));
+
+            let results = run_bounded(&plan.create, |(domain, ip)| {
+                provider.create_dns_record(
+                    &zone_ids[domain],
+                    domain,
+                    record_type_of(ip),
+                    ip,
+                    settings,
+                )
+            });
+
+            let mut success_count = 0;
+            for ((domain, ip), result) in plan.create.iter().zip(results) {
+                let created = result.unwrap_or_else(|e| {
+                    outcome.errors.push(item_error(domain, "→ +", &e, width));
+                    None
+                });
+                if let Some(id) = created {
+                    print!("  "); // 缩进
+                    let formatted_output =
+                        format_dns_operation(&display_name(domain), "→ +", ip, width);
+                    crate::success_println(format_args!("{}", formatted_output));
+                    success_count += 1;
+                    outcome
+                        .domain_ip_map
+                        .entry(domain.clone())
+                        .or_default()
+                        .push(ip.clone());
+                    outcome.created.push((domain.clone(), id, ip.clone()));
+                }
+            }
+
+            crate::info_println(format_args!("总共添加了 {} 个节点", success_count));
+        }
+
+        // 有新IP未写入时保留旧记录，避免域名可用的记录变少
+        let planned_writes = plan.update.len() + plan.create.len();
+        if !plan.delete.is_empty() && outcome.written() < planned_writes {
+            crate::warning_println(format_args!(
+                "有 {} 个节点写入失败，跳过删除 {} 个旧节点",
+                planned_writes - outcome.written(),
+                plan.delete.len()
+            ));
+            for (domain, record) in &plan.delete {
+                outcome
+                    .domain_ip_map
+                    .entry(domain.clone())
+                    .or_default()
+                    .push(record.content.clone());
+            }
+        } else if !plan.delete.is_empty() {
+            crate::print_section_header("删除节点");
+            crate::info_println(format_args!("开始删除 {} 个节点:", plan.delete.len()));
+
+            let results = run_bounded(&plan.delete, |(domain, record)| {
+                provider.delete_dns_record(&zone_ids[domain], &record.id)
+            });
+
+            let mut success_count = 0;
+            for ((domain, record), result) in plan.delete.iter().zip(results) {
+                let deleted = result.unwrap_or_else(|e| {
+                    outcome.errors.push(item_error(domain, "→ -", &e, width));
+                    false
+                });
+                if deleted {
+                    print!("  "); // 缩进
+                    let formatted_output =
+                        format_dns_operation(&display_name(domain), "→ -", &record.content, width);
+                    crate::success_println(format_args!("{}", formatted_output));
+                    success_count += 1;
+                    outcome.deleted.push((domain.clone(), record.clone()));
+                }
+            }
+
+            crate::info_println(format_args!("总共删除了 {} 个节点", success_count));
+        }
+
+        if !plan.has_changes() {
+            crate::info_println(format_args!("DNS记录已是最新，无需变更"));
+        }
+
+        Ok(outcome)
+    }
+
+    fn apply_plan_batch(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_ids: &HashMap<String, String>,
+        plan: &ReconcilePlan,
+        settings: &RecordSettings,
+    ) -> Result<ApplyOutcome> {
+        if !plan.has_changes() {
+            return self.apply_plan(provider, zone_ids, plan, settings);
+        }
+
+        let mut outcome = ApplyOutcome::default();
+        let width = plan.max_domain_width();
+
+        for (zone_id, zone_plan) in plan.split_by_zone(zone_ids) {
+            let result = if !zone_plan.has_changes() {
+                Some(Vec::new())
+            } else if provider.supports_batch() {
+                match provider.batch_dns_records(&zone_id, &zone_plan, settings) {
+                    Ok(created_ids) => Some(created_ids),
+                    Err(e) if e.downcast_ref::<BatchUnsupported>().is_some() => {
+                        crate::warning_println(format_args!("批量接口不可用，改为逐条提交"));
+                        None
+                    }
+                    Err(e) => {
+                        // 批量提交整体不生效，该区域按失败处理，不再逐条重放
+                        crate::error_println(format_args!("{}", e));
+                        let mut failed = zone_plan.failed_outcome();
+                        failed.errors.push(e.to_string());
+                        outcome.merge(failed);
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+
+            let zone_outcome = match result {
+                Some(result) => {
+                    zone_plan.print_unmanaged(width);
+                    zone_plan.print_batch_result(width);
+                    zone_plan.batch_outcome(result)
+                }
+                None => self.apply_plan(provider, zone_ids, &zone_plan, settings)?,
+            };
+            outcome.merge(zone_outcome);
+        }
+
+        Ok(outcome)
+    }
+
+    fn rollback(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_ids: &HashMap<String, String>,
+        outcome: &ApplyOutcome,
+        settings: &RecordSettings,
+        account: &str,
+        required: usize,
+    ) -> RollbackReport {
+        crate::print_section_header("回滚");
+        let width = |d: &String| measure_text_width(&display_name(d));
+        let width = outcome
+            .updated
+            .iter()
+            .chain(&outcome.deleted)
+            .map(|(d, _)| width(d))
+            .chain(outcome.created.iter().map(|(d, _, _)| width(d)))
+            .max()
+            .unwrap_or(0);
+
+        // 先恢复旧记录，再撤销新记录，避免域名出现无记录的空档
+        let mut errors = Vec::new();
+        let mut restored = 0;
+        for (domain, record) in &outcome.updated {
+            let content = &record.content;
+            let result = provider.update_dns_record(
+                &zone_ids[domain],
+                &record.id,
+                domain,
+                &record.record_type,
+                content,
+                &settings.restoring(record),
+            );
+            let updated = result.unwrap_or_else(|e| {
+                errors.push(item_error(domain, "→ ~", &e, width));
+                false
+            });
+            if updated {
+                print!("  "); // 缩进
+                let formatted_output =
+                    format_dns_operation(&display_name(domain), "→ ~", content, width);
+                crate::success_println(format_args!("{}", formatted_output));
+                restored += 1;
+            }
+        }
+        for (domain, record) in &outcome.deleted {
+            let content = &record.content;
+            let result = provider.create_dns_record(
+                &zone_ids[domain],
+                domain,
+                &record.record_type,
+                content,
+                &settings.restoring(record),
+            );
+            let created = result.unwrap_or_else(|e| {
+                errors.push(item_error(domain, "→ +", &e, width));
+                None
+            });
+            if created.is_some() {
+                print!("  "); // 缩进
+                let formatted_output =
+                    format_dns_operation(&display_name(domain), "→ +", content, width);
+                crate::success_println(format_args!("{}", formatted_output));
+                restored += 1;
+            }
+        }
+
+        let mut removed = 0;
+        for (domain, id, ip) in &outcome.created {
+            let deleted = provider
+                .delete_dns_record(&zone_ids[domain], id)
+                .unwrap_or_else(|e| {
+                    errors.push(item_error(domain, "→ -", &e, width));
+                    false
+                });
+            if deleted {
+                print!("  "); // 缩进
+                let formatted_output =
+                    format_dns_operation(&display_name(domain), "→ -", ip, width);
+                crate::success_println(format_args!("{}", formatted_output));
+                removed += 1;
+            }
+        }
+
+        crate::info_println(format_args!(
+            "总共恢复了 {} 个旧节点，撤销了 {} 个新节点",
+            restored, removed
+        ));
+        if !errors.is_empty() {
+            crate::error_println(format_args!("有 {} 项回滚失败", errors.len()));
+        }
+
+        RollbackReport {
+            account: account.to_string(),
+            required,
+            written: outcome.written(),
+            restored,
+            removed,
+            errors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::start::dns_operations::MANAGED_MARKER;
+
+    fn settings() -> RecordSettings {
+        RecordSettings {
+            proxied: false,
+            ttl: 1,
+            comment: String::new(),
+            tags: Vec::new(),
+            min_records: 1,
+            adopt: false,
+        }
+    }
+
+    fn record(id: &str, content: &str, managed: bool) -> DnsRecord {
+        DnsRecord {
+            id: id.to_string(),
+            record_type: record_type_of(content).to_string(),
+            name: "www.example.com".to_string(),
+            content: content.to_string(),
+            ttl: Some(1),
+            proxied: Some(false),
+            comment: managed.then(|| MANAGED_MARKER.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn plan(desired: &[&str], records: Vec<DnsRecord>) -> ReconcilePlan {
+        plan_with(desired, records, &settings())
+    }
+
+    fn plan_with(
+        desired: &[&str],
+        records: Vec<DnsRecord>,
+        settings: &RecordSettings,
+    ) -> ReconcilePlan {
+        let domain = "www.example.com".to_string();
+        let desired: Vec<(String, String)> = desired
+            .iter()
+            .map(|ip| (domain.clone(), ip.to_string()))
+            .collect();
+        plan_reconciliation(&desired, &[(domain, records)], settings)
+    }
+
+    #[test]
+    fn keeps_record_with_same_ip() {
+        let plan = plan(&["1.1.1.1"], vec![record("a", "1.1.1.1", true)]);
+        assert_eq!(plan.keep.len(), 1);
+        assert!(plan.update.is_empty() && plan.create.is_empty() && plan.delete.is_empty());
+    }
+
+    #[test]
+    fn updates_changed_ip_in_place() {
+        let plan = plan(&["2.2.2.2"], vec![record("a", "1.1.1.1", true)]);
+        assert_eq!(plan.update.len(), 1);
+        let (_, old, ip) = &plan.update[0];
+        assert_eq!((old.id.as_str(), ip.as_str()), ("a", "2.2.2.2"));
+        assert!(plan.create.is_empty() && plan.delete.is_empty());
+    }
+
+    #[test]
+    fn deletes_extra_managed_record() {
+        let plan = plan(
+            &["1.1.1.1"],
+            vec![record("a", "1.1.1.1", true), record("b", "3.3.3.3", true)],
+        );
+        assert_eq!(plan.keep.len(), 1);
+        assert_eq!(plan.delete.len(), 1);
+        assert_eq!(plan.delete[0].1.id, "b");
+    }
+
+    #[test]
+    fn creates_missing_ip() {
+        let plan = plan(&["1.1.1.1", "2.2.2.2"], vec![record("a", "1.1.1.1", true)]);
+        assert_eq!(plan.keep.len(), 1);
+        assert_eq!(
+            plan.create,
+            vec![("www.example.com".to_string(), "2.2.2.2".to_string())]
+        );
+        assert!(plan.update.is_empty() && plan.delete.is_empty());
+    }
+
+    #[test]
+    fn leaves_unmanaged_record_alone() {
+        let plan = plan(&["2.2.2.2"], vec![record("a", "1.1.1.1", false)]);
+        assert_eq!(plan.unmanaged.len(), 1);
+        assert_eq!(
+            plan.create,
+            vec![("www.example.com".to_string(), "2.2.2.2".to_string())]
+        );
+        assert!(plan.update.is_empty() && plan.delete.is_empty());
+    }
+
+    #[test]
+    fn warns_about_unmanaged_records_next_to_managed_ones() {
+        let records = vec![record("a", "1.1.1.1", false), record("b", "3.3.3.3", false)];
+        let mixed = plan(&["1.1.1.1", "2.2.2.2"], records);
+        assert_eq!(mixed.unmanaged.len(), 1);
+        assert_eq!(mixed.mixed_domains(), ["www.example.com"]);
+
+        let untouched = plan(&["3.3.3.3"], vec![record("a", "3.3.3.3", false)]);
+        assert!(untouched.mixed_domains().is_empty());
+    }
+
+    #[test]
+    fn adopts_unmarked_records_when_enabled() {
+        let settings = RecordSettings {
+            adopt: true,
+            ..settings()
+        };
+        let records = vec![record("a", "1.1.1.1", false), record("b", "3.3.3.3", false)];
+        let plan = plan_with(&["1.1.1.1", "2.2.2.2"], records, &settings);
+        assert!(plan.unmanaged.is_empty() && plan.create.is_empty());
+        let updates: Vec<(&str, &str)> = plan
+            .update
+            .iter()
+            .map(|(_, r, ip)| (r.id.as_str(), ip.as_str()))
+            .collect();
+        assert_eq!(updates, [("a", "1.1.1.1"), ("b", "2.2.2.2")]);
+        assert_eq!(
+            describe_update(&plan.update[0].1, "1.1.1.1"),
+            "1.1.1.1（接管）"
+        );
+    }
+
+    #[test]
+    fn updates_when_proxied_or_ttl_changes() {
+        let mut proxied = record("a", "1.1.1.1", true);
+        proxied.proxied = Some(true);
+        let plan_proxied = plan(&["1.1.1.1"], vec![proxied]);
+        assert_eq!(plan_proxied.update.len(), 1);
+        assert_eq!(plan_proxied.update[0].2, "1.1.1.1");
+
+        let mut ttl = record("a", "1.1.1.1", true);
+        ttl.ttl = Some(300);
+        let plan_ttl = plan(&["1.1.1.1"], vec![ttl]);
+        assert_eq!(plan_ttl.update.len(), 1);
+        assert!(plan_ttl.keep.is_empty());
+    }
+
+    #[test]
+    fn failed_batch_keeps_existing_ips() {
+        let plan = plan(
+            &["2.2.2.2", "4.4.4.4"],
+            vec![record("a", "1.1.1.1", true), record("b", "3.3.3.3", false)],
+        );
+        let outcome = plan.failed_outcome();
+        assert_eq!(outcome.written(), 0);
+        assert!(!outcome.has_changes());
+        assert_eq!(outcome.domain_ip_map["www.example.com"], ["1.1.1.1"]);
+    }
+
+    #[test]
+    fn rollback_message_lists_errors() {
+        let mut report = RollbackReport {
+            account: "cf".to_string(),
+            required: 2,
+            written: 1,
+            restored: 1,
+            removed: 0,
+            errors: Vec::new(),
+        };
+        assert!(!report.message("test", "IPv4").contains("回滚失败"));
+
+        report.errors.push("www.example.com timeout".to_string());
+        assert!(
+            report
+                .message("test", "IPv4")
+                .ends_with("其中 1 项回滚失败：www.example.com timeout")
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,815 @@
+use super::dns_operations::{
+    DnsProvider, DnsRecord, MANAGED_MARKER, RecordSettings, Zone, indented_error_println, same_name,
+};
+use super::reconcile::{ReconcilePlan, record_type_of};
+use super::signing::{hmac_sha1, hmac_sha256, hmac_sha512, unix_timestamp};
+use crate::Account;
+use anyhow::Result;
+use base64::{Engine as _, engine::general_purpose};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// 连接与读写超时时间
+const RFC2136_TIMEOUT: Duration = Duration::from_secs(30);
+// 未指定端口时使用的 DNS 端口
+const DNS_PORT: u16 = 53;
+// TTL 设置为自动时使用的值
+const RFC2136_DEFAULT_TTL: u32 = 300;
+// TSIG 允许的时间误差（秒）
+const TSIG_FUDGE: u16 = 300;
+// 未指定算法时使用的 TSIG 算法
+const DEFAULT_TSIG_ALGORITHM: &str = "hmac-sha256";
+
+// 报文中用到的类型、类别与操作码
+const TYPE_A: u16 = 1;
+const TYPE_SOA: u16 = 6;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const TYPE_TSIG: u16 = 250;
+const TYPE_AXFR: u16 = 252;
+const CLASS_IN: u16 = 1;
+const CLASS_NONE: u16 = 254;
+const CLASS_ANY: u16 = 255;
+const OPCODE_UPDATE: u16 = 5 << 11;
+
+// 归属记录的主机名前缀，CFRS 新建的每条记录都在此名下有一条 TXT 记录
+const OWNER_PREFIX: &str = "_cfrs.";
+
+/// 响应码的名称
+fn rcode_name(rcode: u16) -> &'static str {
+    match rcode {
+        1 => "FORMERR",
+        2 => "SERVFAIL",
+        3 => "NXDOMAIN",
+        4 => "NOTIMP",
+        5 => "REFUSED",
+        6 => "YXDOMAIN",
+        7 => "YXRRSET",
+        8 => "NXRRSET",
+        9 => "NOTAUTH",
+        10 => "NOTZONE",
+        _ => "UNKNOWN",
+    }
+}
+
+/// TSIG 错误码的名称
+fn tsig_error_name(error: u16) -> &'static str {
+    match error {
+        16 => "BADSIG",
+        17 => "BADKEY",
+        18 => "BADTIME",
+        22 => "BADTRUNC",
+        _ => "UNKNOWN",
+    }
+}
+
+/// 将域名编码为报文格式
+fn wire_name(name: &str) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for label in name
+        .trim_end_matches('.')
+        .split('.')
+        .filter(|l| !l.is_empty())
+    {
+        buf.push(label.len() as u8);
+        buf.extend_from_slice(label.as_bytes());
+    }
+    buf.push(0);
+    buf
+}
+
+/// 记录类型对应的类型值
+fn type_code(record_type: &str) -> u16 {
+    if record_type == "AAAA" {
+        TYPE_AAAA
+    } else {
+        TYPE_A
+    }
+}
+
+/// 将 IP 编码为记录数据
+fn rdata_of(ip: &str) -> Result<Vec<u8>> {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => Ok(v4.octets().to_vec()),
+        Ok(IpAddr::V6(v6)) => Ok(v6.octets().to_vec()),
+        Err(_) => Err(anyhow::anyhow!("无效的IP地址: {}", ip)),
+    }
+}
+
+/// 记录对应的归属 TXT 记录数据，内容为「类型 IP」
+fn owner_rdata(record_type: &str, ip: &str) -> Result<Vec<u8>> {
+    let ip: IpAddr = ip
+        .parse()
+        .map_err(|_| anyhow::anyhow!("无效的IP地址: {}", ip))?;
+    let text = format!("{} {}", record_type, ip);
+    let mut rdata = vec![text.len() as u8];
+    rdata.extend_from_slice(text.as_bytes());
+    Ok(rdata)
+}
+
+/// 由域名、类型与IP组成记录ID，格式与区域文件一致
+fn record_id(domain: &str, record_type: &str, ip: &str) -> String {
+    format!("{} {} {}", domain, record_type, ip)
+}
+
+/// 解析记录ID，返回 (域名, 类型, IP)
+fn parse_record_id(id: &str) -> Result<(&str, &str, &str)> {
+    let mut parts = id.splitn(3, ' ');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(domain), Some(record_type), Some(ip)) => Ok((domain, record_type, ip)),
+        _ => Err(anyhow::anyhow!("无效的记录ID: {}", id)),
+    }
+}
+
+/// 从报文中读取 16 位整数
+fn read_u16(message: &[u8], pos: usize) -> Result<u16> {
+    message
+        .get(pos..pos + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(incomplete)
+}
+
+/// 报文不完整时的错误
+fn incomplete() -> anyhow::Error {
+    anyhow::anyhow!("DNS 响应报文不完整")
+}
+
+/// 读取报文中的域名（支持压缩指针），返回域名与其后的位置
+fn read_name(message: &[u8], mut pos: usize) -> Result<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // 防止恶意报文中的指针循环
+    for _ in 0..128 {
+        let len = *message.get(pos).ok_or_else(incomplete)? as usize;
+        if len == 0 {
+            return Ok((labels.join("."), end.unwrap_or(pos + 1)));
+        }
+        if len & 0xC0 == 0xC0 {
+            end.get_or_insert(pos + 2);
+            pos = (read_u16(message, pos)? & 0x3FFF) as usize;
+            continue;
+        }
+        let label = message.get(pos + 1..pos + 1 + len).ok_or_else(incomplete)?;
+        labels.push(String::from_utf8_lossy(label).to_string());
+        pos += 1 + len;
+    }
+    Err(anyhow::anyhow!("DNS 响应报文中的域名无效"))
+}
+
+/// 报文中的一条资源记录
+struct Record {
+    name: String,
+    rtype: u16,
+    rdata: Vec<u8>,
+}
+
+/// 读取一条资源记录，返回记录与其后的位置
+fn read_record(message: &[u8], pos: usize) -> Result<(Record, usize)> {
+    let (name, pos) = read_name(message, pos)?;
+    let rtype = read_u16(message, pos)?;
+    let rdlen = read_u16(message, pos + 8)? as usize;
+    let end = pos + 10 + rdlen;
+    let rdata = message.get(pos + 10..end).ok_or_else(incomplete)?;
+    let record = Record {
+        name,
+        rtype,
+        rdata: rdata.to_vec(),
+    };
+    Ok((record, end))
+}
+
+/// 收到的响应报文
+struct Response {
+    buf: Vec<u8>,
+    rcode: u16,
+    answers: Vec<Record>,
+    /// 附加段末尾的 TSIG 记录及其在报文中的起始位置
+    tsig: Option<(usize, Record)>,
+}
+
+impl Response {
+    fn parse(buf: Vec<u8>) -> Result<Self> {
+        let rcode = read_u16(&buf, 2)? & 0x000F;
+        let questions = read_u16(&buf, 4)?;
+        let answer_count = read_u16(&buf, 6)? as usize;
+        let total = answer_count + read_u16(&buf, 8)? as usize + read_u16(&buf, 10)? as usize;
+
+        let mut pos = 12;
+        for _ in 0..questions {
+            pos = read_name(&buf, pos)?.1 + 4;
+        }
+
+        let mut answers = Vec::new();
+        let mut tsig = None;
+        for i in 0..total {
+            let (record, next) = read_record(&buf, pos)?;
+            if i < answer_count {
+                answers.push(record);
+            } else if i + 1 == total && record.rtype == TYPE_TSIG {
+                tsig = Some((pos, record));
+            }
+            pos = next;
+        }
+
+        Ok(Self {
+            buf,
+            rcode,
+            answers,
+            tsig,
+        })
+    }
+}
+
+/// 逐字节比较两段 MAC，耗时与内容无关
+fn mac_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 待发送的 DNS 报文
+struct Message {
+    buf: Vec<u8>,
+}
+
+impl Message {
+    /// 创建只包含报头的报文，各段计数随写入自动累加
+    fn new(flags: u16) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let mut buf = Vec::with_capacity(512);
+        buf.extend_from_slice(&(nanos as u16).to_be_bytes());
+        buf.extend_from_slice(&flags.to_be_bytes());
+        buf.extend_from_slice(&[0; 8]);
+        Self { buf }
+    }
+
+    /// 段计数加一，`section` 为 0~3
+    fn count(&mut self, section: usize) {
+        let pos = 4 + section * 2;
+        let value = u16::from_be_bytes([self.buf[pos], self.buf[pos + 1]]) + 1;
+        self.buf[pos..pos + 2].copy_from_slice(&value.to_be_bytes());
+    }
+
+    /// 写入问题段（UPDATE 报文中为区域段）
+    fn question(&mut self, name: &str, rtype: u16) {
+        self.buf.extend_from_slice(&wire_name(name));
+        self.buf.extend_from_slice(&rtype.to_be_bytes());
+        self.buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+        self.count(0);
+    }
+
+    /// 写入更新段的一条资源记录
+    fn update(&mut self, name: &str, rtype: u16, class: u16, ttl: u32, rdata: &[u8]) {
+        self.buf.extend_from_slice(&wire_name(name));
+        self.buf.extend_from_slice(&rtype.to_be_bytes());
+        self.buf.extend_from_slice(&class.to_be_bytes());
+        self.buf.extend_from_slice(&ttl.to_be_bytes());
+        self.buf
+            .extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        self.buf.extend_from_slice(rdata);
+        self.count(2);
+    }
+
+    /// 添加一条记录及其归属记录
+    fn add(&mut self, domain: &str, ip: &str, ttl: u32) -> Result<()> {
+        let record_type = record_type_of(ip);
+        let owner = format!("{}{}", OWNER_PREFIX, domain);
+        self.update(
+            domain,
+            type_code(record_type),
+            CLASS_IN,
+            ttl,
+            &rdata_of(ip)?,
+        );
+        self.update(
+            &owner,
+            TYPE_TXT,
+            CLASS_IN,
+            ttl,
+            &owner_rdata(record_type, ip)?,
+        );
+        Ok(())
+    }
+
+    /// 删除一条指定内容的记录及其归属记录
+    fn delete(&mut self, id: &str) -> Result<()> {
+        let (domain, record_type, ip) = parse_record_id(id)?;
+        let owner = format!("{}{}", OWNER_PREFIX, domain);
+        self.update(
+            domain,
+            type_code(record_type),
+            CLASS_NONE,
+            0,
+            &rdata_of(ip)?,
+        );
+        self.update(
+            &owner,
+            TYPE_TXT,
+            CLASS_NONE,
+            0,
+            &owner_rdata(record_type, ip)?,
+        );
+        Ok(())
+    }
+}
+
+/// TSIG 密钥
+struct TsigKey {
+    name: String,
+    algorithm: String,
+    secret: String,
+}
+
+impl TsigKey {
+    /// 按密钥的算法计算 MAC
+    fn mac(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let secret = general_purpose::STANDARD
+            .decode(self.secret.trim())
+            .map_err(|e| anyhow::anyhow!("TSIG 密钥不是有效的 Base64: {}", e))?;
+        match self.algorithm.as_str() {
+            "hmac-sha1" => Ok(hmac_sha1(&secret, data)),
+            "hmac-sha256" => Ok(hmac_sha256(&secret, data)),
+            "hmac-sha512" => Ok(hmac_sha512(&secret, data)),
+            other => Err(anyhow::anyhow!("不支持的 TSIG 算法: {}", other)),
+        }
+    }
+
+    /// 按 RFC 8945 为报文签名并追加 TSIG 记录，返回请求的 MAC
+    fn sign(&self, message: &mut Message) -> Result<Vec<u8>> {
+        let key_name = wire_name(&self.name.to_ascii_lowercase());
+        let algorithm = wire_name(&self.algorithm);
+        let time_signed = &unix_timestamp().to_be_bytes()[2..];
+
+        let mut data = message.buf.clone();
+        data.extend_from_slice(&key_name);
+        data.extend_from_slice(&CLASS_ANY.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(&algorithm);
+        data.extend_from_slice(time_signed);
+        data.extend_from_slice(&TSIG_FUDGE.to_be_bytes());
+        // 错误码与附加数据长度
+        data.extend_from_slice(&[0; 4]);
+
+        let mac = self.mac(&data)?;
+
+        let mut rdata = algorithm;
+        rdata.extend_from_slice(time_signed);
+        rdata.extend_from_slice(&TSIG_FUDGE.to_be_bytes());
+        rdata.extend_from_slice(&(mac.len() as u16).to_be_bytes());
+        rdata.extend_from_slice(&mac);
+        rdata.extend_from_slice(&message.buf[0..2]);
+        rdata.extend_from_slice(&[0; 4]);
+
+        message.buf.extend_from_slice(&key_name);
+        message.buf.extend_from_slice(&TYPE_TSIG.to_be_bytes());
+        message.buf.extend_from_slice(&CLASS_ANY.to_be_bytes());
+        message.buf.extend_from_slice(&0u32.to_be_bytes());
+        message
+            .buf
+            .extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        message.buf.extend_from_slice(&rdata);
+        message.count(3);
+        Ok(mac)
+    }
+
+    /// 按 RFC 8945 校验响应的 TSIG，返回该响应的 MAC
+    ///
+    /// `prior_mac` 为请求或上一条已签名响应的 MAC，`unsigned` 为其后未签名的响应；
+    /// 区域传送中第一条之后的响应只对时间参数签名。
+    fn verify(
+        &self,
+        response: &Response,
+        prior_mac: &[u8],
+        unsigned: &[u8],
+        first: bool,
+    ) -> Result<Vec<u8>> {
+        let Some((start, record)) = &response.tsig else {
+            return Err(anyhow::anyhow!("DNS 响应没有 TSIG 签名"));
+        };
+        if !same_name(&record.name, &self.name) {
+            return Err(anyhow::anyhow!("DNS 响应的 TSIG 密钥名不匹配"));
+        }
+
+        let rdata = &record.rdata;
+        let (algorithm, pos) = read_name(rdata, 0)?;
+        if !algorithm.eq_ignore_ascii_case(&self.algorithm) {
+            return Err(anyhow::anyhow!("DNS 响应的 TSIG 算法不匹配"));
+        }
+        let time_signed = rdata.get(pos..pos + 6).ok_or_else(incomplete)?;
+        let fudge = read_u16(rdata, pos + 6)?;
+        let mac_len = read_u16(rdata, pos + 8)? as usize;
+        let mac = rdata
+            .get(pos + 10..pos + 10 + mac_len)
+            .ok_or_else(incomplete)?;
+        let original_id = rdata
+            .get(pos + 10 + mac_len..pos + 12 + mac_len)
+            .ok_or_else(incomplete)?;
+        // 错误码、附加数据长度与附加数据
+        let other = rdata.get(pos + 12 + mac_len..).ok_or_else(incomplete)?;
+        match read_u16(other, 0)? {
+            0 => {}
+            error => {
+                return Err(anyhow::anyhow!(
+                    "服务器拒绝了 TSIG 签名 ({})",
+                    tsig_error_name(error)
+                ));
+            }
+        }
+
+        let mut data = (prior_mac.len() as u16).to_be_bytes().to_vec();
+        data.extend_from_slice(prior_mac);
+        data.extend_from_slice(unsigned);
+        // 去掉 TSIG 记录后的报文，报文ID还原为签名时的值
+        let mut message = response.buf[..*start].to_vec();
+        message[0..2].copy_from_slice(original_id);
+        let additional = read_u16(&message, 10)? - 1;
+        message[10..12].copy_from_slice(&additional.to_be_bytes());
+        data.extend_from_slice(&message);
+        if first {
+            data.extend_from_slice(&wire_name(&self.name.to_ascii_lowercase()));
+            data.extend_from_slice(&CLASS_ANY.to_be_bytes());
+            data.extend_from_slice(&0u32.to_be_bytes());
+            data.extend_from_slice(&wire_name(&algorithm.to_ascii_lowercase()));
+            data.extend_from_slice(time_signed);
+            data.extend_from_slice(&fudge.to_be_bytes());
+            data.extend_from_slice(other);
+        } else {
+            data.extend_from_slice(time_signed);
+            data.extend_from_slice(&fudge.to_be_bytes());
+        }
+
+        if !mac_eq(&self.mac(&data)?, mac) {
+            return Err(anyhow::anyhow!("DNS 响应的 TSIG 签名校验失败"));
+        }
+        let mut signed_at = [0u8; 8];
+        signed_at[2..].copy_from_slice(time_signed);
+        if unix_timestamp().abs_diff(u64::from_be_bytes(signed_at)) > fudge as u64 {
+            return Err(anyhow::anyhow!("DNS 响应的 TSIG 时间超出允许误差"));
+        }
+        Ok(mac.to_vec())
+    }
+}
+
+/// 通过 RFC 2136 动态更新维护自建 DNS 服务器（BIND、Knot 等）上的记录
+///
+/// DNS 记录没有备注，CFRS 新建记录时在 `_cfrs.<主机名>` 下写入一条内容为「类型 IP」的
+/// TXT 记录作为归属标记，只有带标记的 A/AAAA 记录才视为托管记录。
+pub struct Rfc2136Provider {
+    server: String,
+    zone: String,
+    tsig: Option<TsigKey>,
+}
+
+impl Rfc2136Provider {
+    pub fn new(account: &Account) -> Self {
+        let tsig = (!account.tsig_key_name.trim().is_empty()).then(|| TsigKey {
+            name: account.tsig_key_name.trim().to_string(),
+            algorithm: match account.tsig_algorithm.trim() {
+                "" => DEFAULT_TSIG_ALGORITHM.to_string(),
+                algorithm => algorithm.to_ascii_lowercase(),
+            },
+            secret: account.tsig_secret.clone(),
+        });
+        Self {
+            server: account.server.trim().to_string(),
+            zone: account.zone_id.trim().trim_end_matches('.').to_string(),
+            tsig,
+        }
+    }
+
+    /// 解析服务器地址，未写端口时使用 53
+    fn server_addr(&self) -> Result<SocketAddr> {
+        let server = self.server.as_str();
+        if server.is_empty() {
+            return Err(anyhow::anyhow!("未设置 RFC 2136 服务器地址"));
+        }
+        if let Ok(addr) = server.parse::<SocketAddr>() {
+            return Ok(addr);
+        }
+        if let Ok(ip) = server.trim_matches(['[', ']']).parse::<IpAddr>() {
+            return Ok(SocketAddr::new(ip, DNS_PORT));
+        }
+        let target = if server.contains(':') {
+            server.to_string()
+        } else {
+            format!("{}:{}", server, DNS_PORT)
+        };
+        target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("无法解析服务器地址: {}", server))
+    }
+
+    /// 签名后通过 TCP 发送报文并读取响应，直到 `done` 返回 true
+    ///
+    /// 设置了 TSIG 密钥时逐条校验响应的签名，区域传送中间的响应可以不签名。
+    fn exchange_all(
+        &self,
+        mut message: Message,
+        mut done: impl FnMut(&Response) -> bool,
+    ) -> Result<Vec<Response>> {
+        let request_mac = match &self.tsig {
+            Some(tsig) => tsig.sign(&mut message)?,
+            None => Vec::new(),
+        };
+
+        let mut stream = TcpStream::connect_timeout(&self.server_addr()?, RFC2136_TIMEOUT)?;
+        stream.set_read_timeout(Some(RFC2136_TIMEOUT))?;
+        stream.set_write_timeout(Some(RFC2136_TIMEOUT))?;
+
+        let mut packet = (message.buf.len() as u16).to_be_bytes().to_vec();
+        packet.extend_from_slice(&message.buf);
+        stream.write_all(&packet)?;
+
+        let mut responses = Vec::new();
+        let mut prior_mac = request_mac;
+        let mut unsigned = Vec::new();
+        loop {
+            let mut len = [0u8; 2];
+            stream.read_exact(&mut len)?;
+            let mut buf = vec![0u8; u16::from_be_bytes(len) as usize];
+            stream.read_exact(&mut buf)?;
+
+            if buf.get(0..2) != message.buf.get(0..2) {
+                return Err(anyhow::anyhow!("DNS 响应的报文ID不匹配"));
+            }
+            let response = Response::parse(buf)?;
+            let finished = done(&response);
+
+            if let Some(tsig) = &self.tsig {
+                let first = responses.is_empty();
+                if response.tsig.is_some() {
+                    prior_mac = tsig.verify(&response, &prior_mac, &unsigned, first)?;
+                    unsigned.clear();
+                } else if response.rcode != 0 {
+                    return Err(anyhow::anyhow!(
+                        "服务器拒绝请求 ({})",
+                        rcode_name(response.rcode)
+                    ));
+                } else if first || finished {
+                    return Err(anyhow::anyhow!("DNS 响应没有 TSIG 签名"));
+                } else {
+                    unsigned.extend_from_slice(&response.buf);
+                }
+            }
+
+            responses.push(response);
+            if finished {
+                return Ok(responses);
+            }
+        }
+    }
+
+    /// 发送报文并读取一条响应
+    fn exchange(&self, message: Message) -> Result<Response> {
+        let mut responses = self.exchange_all(message, |_| true)?;
+        responses.pop().ok_or_else(incomplete)
+    }
+
+    /// 通过 AXFR 读取区域内的全部记录，结果不含泛解析代答的记录
+    fn transfer(&self, zone: &str) -> Result<Vec<Record>> {
+        let mut message = Message::new(0);
+        message.question(zone, TYPE_AXFR);
+
+        // 区域数据以 SOA 开始并以 SOA 结束
+        let mut soa = 0;
+        let responses = self.exchange_all(message, |response| {
+            soa += response
+                .answers
+                .iter()
+                .filter(|r| r.rtype == TYPE_SOA)
+                .count();
+            response.rcode != 0 || response.answers.is_empty() || soa >= 2
+        })?;
+
+        let mut records = Vec::new();
+        for response in responses {
+            if response.rcode != 0 {
+                return Err(anyhow::anyhow!(
+                    "区域传送失败 ({})",
+                    rcode_name(response.rcode)
+                ));
+            }
+            records.extend(response.answers);
+        }
+        if soa < 2 {
+            return Err(anyhow::anyhow!("区域传送不完整"));
+        }
+        Ok(records)
+    }
+
+    /// 发送 UPDATE 报文，响应码非 NOERROR 时返回错误
+    fn send_update(&self, message: Message) -> Result<()> {
+        match self.exchange(message)?.rcode {
+            0 => Ok(()),
+            rcode => Err(anyhow::anyhow!("服务器拒绝更新 ({})", rcode_name(rcode))),
+        }
+    }
+
+    /// 创建区域为本账户区域的 UPDATE 报文
+    fn update_message(&self, zone_id: &str) -> Message {
+        let mut message = Message::new(OPCODE_UPDATE);
+        message.question(zone_id, TYPE_SOA);
+        message
+    }
+
+    /// 记录的 TTL，开启代理的设置对自建服务器无效
+    fn ttl(settings: &RecordSettings) -> u32 {
+        if settings.ttl <= 1 {
+            RFC2136_DEFAULT_TTL
+        } else {
+            settings.ttl
+        }
+    }
+}
+
+impl DnsProvider for Rfc2136Provider {
+    fn name(&self) -> &'static str {
+        "RFC 2136"
+    }
+
+    fn validate(&self) -> Result<()> {
+        crate::print_section_header("RFC 2136 服务器验证");
+
+        let zones = self.list_zones()?;
+        crate::success_println(format_args!(
+            "已连接 {}，区域 {} 可用",
+            self.server, zones[0].name
+        ));
+        Ok(())
+    }
+
+    /// 无法列出服务器上的区域，仅通过查询 SOA 确认账户填写的区域
+    fn list_zones(&self) -> Result<Vec<Zone>> {
+        if self.zone.is_empty() {
+            return Err(anyhow::anyhow!("RFC 2136 账户需要在 zone_id 中填写区域名"));
+        }
+
+        let mut message = Message::new(0);
+        message.question(&self.zone, TYPE_SOA);
+        match self.exchange(message)?.rcode {
+            0 => Ok(vec![Zone {
+                id: self.zone.clone(),
+                name: self.zone.clone(),
+            }]),
+            rcode => Err(anyhow::anyhow!(
+                "查询区域 {} 失败 ({})",
+                self.zone,
+                rcode_name(rcode)
+            )),
+        }
+    }
+
+    fn get_dns_records(
+        &self,
+        zone_id: &str,
+        domain: &str,
+        record_type: Option<&str>,
+    ) -> Result<Vec<DnsRecord>> {
+        Ok(self
+            .list_zone_records(zone_id)?
+            .into_iter()
+            .filter(|r| same_name(&r.name, domain))
+            .filter(|r| record_type.is_none_or(|rt| r.record_type == rt))
+            .collect())
+    }
+
+    fn supports_zone_listing(&self) -> bool {
+        true
+    }
+
+    fn list_zone_records(&self, zone_id: &str) -> Result<Vec<DnsRecord>> {
+        let records = self
+            .transfer(zone_id)
+            .map_err(|e| anyhow::anyhow!("获取DNS记录失败: {}", e))?;
+
+        // 归属记录：(主机名, 归属 TXT 记录数据)
+        let owned: HashSet<(String, &[u8])> = records
+            .iter()
+            .filter(|r| r.rtype == TYPE_TXT)
+            .filter_map(|r| {
+                let name = r.name.to_ascii_lowercase();
+                let domain = name.strip_prefix(OWNER_PREFIX)?.to_string();
+                Some((domain, r.rdata.as_slice()))
+            })
+            .collect();
+
+        let mut result = Vec::new();
+        for record in &records {
+            let (record_type, ip) = match (record.rtype, record.rdata.len()) {
+                (TYPE_A, 4) => (
+                    "A",
+                    Ipv4Addr::from(<[u8; 4]>::try_from(&record.rdata[..])?).to_string(),
+                ),
+                (TYPE_AAAA, 16) => (
+                    "AAAA",
+                    Ipv6Addr::from(<[u8; 16]>::try_from(&record.rdata[..])?).to_string(),
+                ),
+                _ => continue,
+            };
+            let owner = owner_rdata(record_type, &ip)?;
+            let managed = owned.contains(&(record.name.to_ascii_lowercase(), owner.as_slice()));
+            // 自动 TTL 写入时换算为固定值，不返回 TTL 以免被视为需要更新
+            result.push(DnsRecord {
+                id: record_id(&record.name, record_type, &ip),
+                record_type: record_type.to_string(),
+                name: record.name.clone(),
+                content: ip,
+                comment: managed.then(|| MANAGED_MARKER.to_string()),
+                ..Default::default()
+            });
+        }
+        Ok(result)
+    }
+
+    fn delete_dns_record(&self, zone_id: &str, record_id: &str) -> Result<bool> {
+        let mut message = self.update_message(zone_id);
+        message.delete(record_id)?;
+
+        match self.send_update(message) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                indented_error_println(format_args!("删除DNS记录失败: {}", e));
+                Ok(false)
+            }
+        }
+    }
+
+    fn create_dns_record(
+        &self,
+        zone_id: &str,
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<Option<String>> {
+        let mut message = self.update_message(zone_id);
+        message.add(domain, ip, Self::ttl(settings))?;
+
+        match self.send_update(message) {
+            Ok(()) => Ok(Some(record_id(domain, record_type, ip))),
+            Err(e) => {
+                indented_error_println(format_args!("添加DNS记录失败: {}", e));
+                Ok(None)
+            }
+        }
+    }
+
+    fn update_dns_record(
+        &self,
+        zone_id: &str,
+        record_id: &str,
+        domain: &str,
+        _record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<bool> {
+        // 同一报文中删除旧内容并添加新内容，服务器会原子地处理
+        let mut message = self.update_message(zone_id);
+        message.delete(record_id)?;
+        message.add(domain, ip, Self::ttl(settings))?;
+
+        match self.send_update(message) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                indented_error_println(format_args!("更新DNS记录失败: {}", e));
+                Ok(false)
+            }
+        }
+    }
+
+    fn supports_batch(&self) -> bool {
+        true
+    }
+
+    fn batch_dns_records(
+        &self,
+        zone_id: &str,
+        plan: &ReconcilePlan,
+        settings: &RecordSettings,
+    ) -> Result<Vec<String>> {
+        let ttl = Self::ttl(settings);
+        let mut message = self.update_message(zone_id);
+        for (_, record) in &plan.delete {
+            message.delete(&record.id)?;
+        }
+        for (domain, record, ip) in &plan.update {
+            message.delete(&record.id)?;
+            message.add(domain, ip, ttl)?;
+        }
+        for (domain, ip) in &plan.create {
+            message.add(domain, ip, ttl)?;
+        }
+
+        self.send_update(message)
+            .map_err(|e| anyhow::anyhow!("批量提交DNS记录失败: {}", e))?;
+        Ok(plan
+            .create
+            .iter()
+            .map(|(domain, ip)| record_id(domain, record_type_of(ip), ip))
+            .collect())
+    }
+}
\ No newline at end of file
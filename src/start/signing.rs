@@ -0,0 +1,87 @@
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 计算 HMAC-SHA256
+pub fn hmac_sha256(key: &[u8], data: impl AsRef<[u8]>) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data.as_ref());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 计算 HMAC-SHA1
+pub fn hmac_sha1(key: &[u8], data: impl AsRef<[u8]>) -> Vec<u8> {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data.as_ref());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 计算 HMAC-SHA512
+pub fn hmac_sha512(key: &[u8], data: impl AsRef<[u8]>) -> Vec<u8> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data.as_ref());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 计算 SHA256 并输出小写十六进制
+pub fn sha256_hex(data: &str) -> String {
+    hex(&Sha256::digest(data.as_bytes()))
+}
+
+/// 小写十六进制编码
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 按 RFC 3986 进行百分号编码，仅保留非保留字符
+pub fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// 当前 Unix 时间戳（秒）
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 将 Unix 时间戳转换为 UTC 的 (年, 月, 日, 时, 分, 秒)
+pub fn utc_datetime(timestamp: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (timestamp / 86400) as i64;
+    let secs = (timestamp % 86400) as u32;
+
+    // 按公历推算日期，算法来自 Howard Hinnant 的 civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day, secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+/// 生成请求唯一的随机串
+pub fn nonce() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}{:x}{:x}", nanos, std::process::id(), count)
+}
\ No newline at end of file
@@ -0,0 +1,359 @@
+use super::dns_operations::{
+    DnsProvider, DnsRecord, RecordSettings, Zone, indented_error_println, same_name,
+};
+use super::reconcile::{ReconcilePlan, record_type_of};
+use super::signing::{nonce, unix_timestamp, utc_datetime};
+use crate::Account;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// 未指定文件时使用的模拟记录文件
+const DEFAULT_RECORDS_FILE: &str = "cfrs-simulate.json";
+
+/// 与 Cloudflare 错误码一致的模拟错误
+#[derive(Debug)]
+struct SimulatedError {
+    code: u32,
+    message: &'static str,
+}
+
+impl std::fmt::Display for SimulatedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for SimulatedError {}
+
+// 已有相同记录
+const DUPLICATE_RECORD: SimulatedError = SimulatedError {
+    code: 81057,
+    message: "An identical record already exists.",
+};
+// 记录不存在
+const RECORD_NOT_FOUND: SimulatedError = SimulatedError {
+    code: 81044,
+    message: "Record does not exist.",
+};
+
+/// 当前 UTC 时间，格式与 Cloudflare 的 modified_on 一致
+fn modified_on() -> String {
+    let (year, month, day, hour, minute, second) = utc_datetime(unix_timestamp());
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// 模拟文件中的一条记录，字段与 Cloudflare 的记录保持一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SimulatedRecord {
+    id: String,
+    zone_id: String,
+    #[serde(rename = "type")]
+    record_type: String,
+    name: String,
+    content: String,
+    ttl: u32,
+    proxied: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified_on: Option<String>,
+}
+
+impl From<SimulatedRecord> for DnsRecord {
+    fn from(r: SimulatedRecord) -> Self {
+        DnsRecord {
+            id: r.id,
+            record_type: r.record_type,
+            name: r.name,
+            content: r.content,
+            ttl: Some(r.ttl),
+            proxied: Some(r.proxied),
+            comment: r.comment,
+            tags: r.tags,
+            modified_on: r.modified_on,
+        }
+    }
+}
+
+/// 模拟文件的全部内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SimulatedState {
+    #[serde(default)]
+    zones: Vec<Zone>,
+    #[serde(default)]
+    records: Vec<SimulatedRecord>,
+}
+
+impl SimulatedState {
+    /// 新建记录，已有相同记录时返回 81057 错误
+    fn create(
+        &mut self,
+        zone_id: &str,
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<String> {
+        let duplicate = self.records.iter().any(|r| {
+            r.zone_id == zone_id
+                && r.record_type == record_type
+                && same_name(&r.name, domain)
+                && r.content == ip
+        });
+        if duplicate {
+            return Err(DUPLICATE_RECORD.into());
+        }
+
+        let id = nonce();
+        self.records.push(SimulatedRecord {
+            id: id.clone(),
+            zone_id: zone_id.to_string(),
+            record_type: record_type.to_string(),
+            name: domain.to_string(),
+            content: ip.to_string(),
+            ttl: settings.effective_ttl(),
+            proxied: settings.proxied,
+            comment: Some(settings.managed_comment()),
+            tags: settings.tags.clone(),
+            modified_on: Some(modified_on()),
+        });
+        Ok(id)
+    }
+
+    /// 原地更新记录内容
+    fn update(
+        &mut self,
+        zone_id: &str,
+        record_id: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<()> {
+        let record = self
+            .records
+            .iter_mut()
+            .find(|r| r.zone_id == zone_id && r.id == record_id)
+            .ok_or(RECORD_NOT_FOUND)?;
+        record.record_type = record_type_of(ip).to_string();
+        record.content = ip.to_string();
+        record.ttl = settings.effective_ttl();
+        record.proxied = settings.proxied;
+        record.comment = Some(settings.managed_comment());
+        record.tags = settings.tags.clone();
+        record.modified_on = Some(modified_on());
+        Ok(())
+    }
+
+    /// 删除记录
+    fn delete(&mut self, zone_id: &str, record_id: &str) -> Result<()> {
+        let before = self.records.len();
+        self.records
+            .retain(|r| !(r.zone_id == zone_id && r.id == record_id));
+        if self.records.len() == before {
+            return Err(RECORD_NOT_FOUND.into());
+        }
+        Ok(())
+    }
+}
+
+/// 把记录保存在本地 JSON 文件中的离线模拟服务商，用于在不接触线上区域的情况下试运行解析组
+pub struct SimulatedProvider {
+    path: PathBuf,
+    zone: String,
+    // 并发写入时串行化对文件的读改写
+    lock: Mutex<()>,
+}
+
+impl SimulatedProvider {
+    pub fn new(account: &Account) -> Self {
+        let path = match account.records_file.trim() {
+            "" => DEFAULT_RECORDS_FILE,
+            file => file,
+        };
+        Self {
+            path: PathBuf::from(path),
+            zone: account.zone_id.trim().to_string(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// 读取模拟文件，文件不存在时以账户填写的区域创建空文件内容
+    fn load(&self) -> Result<SimulatedState> {
+        if !self.path.exists() {
+            let zones = if self.zone.is_empty() {
+                Vec::new()
+            } else {
+                vec![Zone {
+                    id: self.zone.clone(),
+                    name: self.zone.clone(),
+                }]
+            };
+            return Ok(SimulatedState {
+                zones,
+                records: Vec::new(),
+            });
+        }
+
+        let content = fs::read_to_string(&self.path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("模拟文件 {} 格式错误: {}", self.path.display(), e))
+    }
+
+    /// 写回模拟文件
+    fn save(&self, state: &SimulatedState) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(state)?)?;
+        Ok(())
+    }
+
+    /// 读取、修改并写回模拟文件，修改失败时文件保持不变
+    fn modify<T>(&self, f: impl FnOnce(&mut SimulatedState) -> Result<T>) -> Result<T> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut state = self.load()?;
+        let value = f(&mut state)?;
+        self.save(&state)?;
+        Ok(value)
+    }
+}
+
+impl DnsProvider for SimulatedProvider {
+    fn name(&self) -> &'static str {
+        "离线模拟"
+    }
+
+    fn validate(&self) -> Result<()> {
+        crate::print_section_header("离线模拟");
+
+        let state = self.load()?;
+        crate::success_println(format_args!(
+            "使用模拟文件 {}，共有 {} 个区域、{} 条记录",
+            self.path.display(),
+            state.zones.len(),
+            state.records.len()
+        ));
+        Ok(())
+    }
+
+    fn list_zones(&self) -> Result<Vec<Zone>> {
+        Ok(self.load()?.zones)
+    }
+
+    fn get_dns_records(
+        &self,
+        zone_id: &str,
+        domain: &str,
+        record_type: Option<&str>,
+    ) -> Result<Vec<DnsRecord>> {
+        Ok(self
+            .load()?
+            .records
+            .into_iter()
+            .filter(|r| {
+                r.zone_id == zone_id
+                    && same_name(&r.name, domain)
+                    && record_type.is_none_or(|rt| r.record_type == rt)
+            })
+            .map(DnsRecord::from)
+            .collect())
+    }
+
+    fn supports_zone_listing(&self) -> bool {
+        true
+    }
+
+    fn list_zone_records(&self, zone_id: &str) -> Result<Vec<DnsRecord>> {
+        Ok(self
+            .load()?
+            .records
+            .into_iter()
+            .filter(|r| r.zone_id == zone_id)
+            .map(DnsRecord::from)
+            .collect())
+    }
+
+    fn delete_dns_record(&self, zone_id: &str, record_id: &str) -> Result<bool> {
+        match self.modify(|state| state.delete(zone_id, record_id)) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                indented_error_println(format_args!("删除DNS记录失败: {}", e));
+                Ok(false)
+            }
+        }
+    }
+
+    fn create_dns_record(
+        &self,
+        zone_id: &str,
+        domain: &str,
+        record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<Option<String>> {
+        match self.modify(|state| state.create(zone_id, domain, record_type, ip, settings)) {
+            Ok(id) => Ok(Some(id)),
+            // 与 Cloudflare 相同，已有相同记录时不需要更新
+            Err(e)
+                if e.downcast_ref::<SimulatedError>()
+                    .is_some_and(|e| e.code == DUPLICATE_RECORD.code) =>
+            {
+                print!("  ");
+                crate::warning_println(format_args!("已有 {} 的记录，不做更新", ip));
+                Ok(None)
+            }
+            Err(e) => {
+                indented_error_println(format_args!("添加DNS记录失败: {}", e));
+                Ok(None)
+            }
+        }
+    }
+
+    fn update_dns_record(
+        &self,
+        zone_id: &str,
+        record_id: &str,
+        _domain: &str,
+        _record_type: &str,
+        ip: &str,
+        settings: &RecordSettings,
+    ) -> Result<bool> {
+        match self.modify(|state| state.update(zone_id, record_id, ip, settings)) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                indented_error_println(format_args!("更新DNS记录失败: {}", e));
+                Ok(false)
+            }
+        }
+    }
+
+    fn supports_batch(&self) -> bool {
+        true
+    }
+
+    /// 与 Cloudflare 批量接口一致，按删除、更新、新建的顺序执行，任一失败则整体不生效
+    fn batch_dns_records(
+        &self,
+        zone_id: &str,
+        plan: &ReconcilePlan,
+        settings: &RecordSettings,
+    ) -> Result<Vec<String>> {
+        self.modify(|state| {
+            for (_, record) in &plan.delete {
+                state.delete(zone_id, &record.id)?;
+            }
+            for (_, record, ip) in &plan.update {
+                state.update(zone_id, &record.id, ip, settings)?;
+            }
+            plan.create
+                .iter()
+                .map(|(domain, ip)| state.create(zone_id, domain, record_type_of(ip), ip, settings))
+                .collect()
+        })
+        .map_err(|e| anyhow::anyhow!("批量提交DNS记录失败: {}", e))
+    }
+}
\ No newline at end of file
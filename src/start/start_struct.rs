@@ -1,28 +1,43 @@
-use super::ddns_operations::DdnsOperations;
-use super::utils::{create_domain_ip_mapping, get_result_csv_path};
+use super::cloudflare_api::RequestBudget;
+use super::ddns_operations::{DdnsOperations, DdnsTarget};
+use super::dns_operations::RecordSettings;
+use super::dns_operations::Zone;
+use super::ip_source::source_list;
+use super::tester::Tester;
+use super::zone_operations::ZoneRecords;
 use crate::push::PushService;
-use crate::{Config, Resolve, Settings, UIComponents, clear_screen, error_println, impl_settings};
+use crate::{
+    Account, Config, IpSource, Resolve, Settings, UIComponents, clear_screen, error_println,
+    impl_settings,
+};
 use anyhow::Result;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 // 为execute_push函数创建参数结构体
 pub struct PushParams<'a> {
     pub push_mod: &'a str,
-    pub hostnames: &'a str,
     pub v4_num: u32,
     pub v6_num: u32,
     pub ip_type: &'a str,
     pub ddns_name: &'a str,
-    pub ips: &'a [String],
-    pub domains: &'a [String],
-    pub add_ddns: &'a str,
+    pub domain_ip_mapping: &'a [(String, String)],
     pub csvfile: &'a str,
 }
 
+/// 解析组实际使用的测速参数和地址来源
+pub struct TestSettings<'a> {
+    pub cf_command: &'a str,
+    pub v4_sources: Vec<IpSource>,
+    pub v6_sources: Vec<IpSource>,
+    pub cidr_sample: u32,
+    pub builtin_sample: u32,
+}
+
 // 为run_push函数创建参数结构体
 struct RunPushParams<'a> {
     push_mod: &'a str,
-    hostnames: &'a str,
     v4_num: u32,
     v6_num: u32,
     ip_type: &'a str,
@@ -31,11 +46,47 @@ struct RunPushParams<'a> {
     domain_ip_mapping: &'a [(String, String)],
 }
 
+/// 解析组的执行方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunMode {
+    /// 正常执行，修改DNS并推送
+    Apply,
+    /// 仅预览DNS变更，不修改任何记录也不推送
+    Plan {
+        /// 是否复用已有的测速结果而不重新测速
+        reuse_result: bool,
+    },
+}
+
+impl RunMode {
+    pub fn is_plan(&self) -> bool {
+        matches!(self, RunMode::Plan { .. })
+    }
+
+    /// 是否跳过测速直接读取已有结果
+    pub fn reuse_result(&self) -> bool {
+        matches!(self, RunMode::Plan { reuse_result: true })
+    }
+}
+
 pub struct Start {
     config_path: PathBuf,
     config: Config,
     ui: UIComponents,
     push_service: PushService,
+    // 各账户的区域列表缓存，键为账户组名称
+    pub(super) zone_cache: RefCell<HashMap<String, Vec<Zone>>>,
+    // 各区域的记录缓存，在本次运行内共用
+    pub(super) record_cache: RefCell<HashMap<String, ZoneRecords>>,
+    // 本次运行剩余的 Cloudflare 请求次数
+    pub(super) request_budget: RequestBudget,
+    // 共享测速配置本次运行的测速结果文件内容，键为 "配置名/IP类型"
+    pub(super) test_results: RefCell<HashMap<String, String>>,
+    // 本次运行中各共享测速配置需要测速的 IPv4 和 IPv6 数量
+    pub(super) test_counts: HashMap<String, (u32, u32)>,
+    pub(super) mode: RunMode,
+    // 配置的测速程序
+    pub(super) tester: Tester,
 }
 
 impl Start {
@@ -46,16 +97,32 @@ impl Start {
             config: Config::default(),
             ui: UIComponents::new(),
             push_service: PushService::new(&config_path_buf)?,
+            zone_cache: RefCell::new(HashMap::new()),
+            record_cache: RefCell::new(HashMap::new()),
+            request_budget: RequestBudget::from_env(),
+            test_results: RefCell::new(HashMap::new()),
+            test_counts: HashMap::new(),
+            mode: RunMode::Apply,
+            tester: Tester::from_config(None),
         };
         settings.load_config()?;
+        settings.tester = Tester::from_config(settings.config.tester.as_ref());
         Ok(settings)
     }
 
-    pub fn run(&mut self, ddns_name: Option<String>) -> Result<()> {
-        if let Some(name) = ddns_name {
-            // 直接执行指定解析组
-            self.execute_resolve_by_name(&name)?;
-            return Ok(());
+    pub fn run(&mut self, ddns_names: Vec<String>, plan: bool) -> Result<()> {
+        if !ddns_names.is_empty() {
+            // 直接执行指定解析组，预览模式下有测速结果则直接复用
+            let resolves = ddns_names
+                .iter()
+                .map(|name| self.find_resolve(name))
+                .collect::<Result<Vec<_>>>()?;
+            if plan {
+                self.mode = RunMode::Plan {
+                    reuse_result: resolves.iter().all(|r| self.has_result_csv(r)),
+                };
+            }
+            return self.execute_resolves(&resolves);
         }
 
         // 交互模式
@@ -68,10 +135,11 @@ impl Start {
             return Ok(());
         }
 
-        let items_ref = resolves
+        let mut items_ref = resolves
             .iter()
             .map(|r| r.ddns_name.as_str())
             .collect::<Vec<_>>();
+        items_ref.push("全部解析组");
 
         let selection = match self
             .ui
@@ -81,51 +149,146 @@ impl Start {
             None => return Ok(()),
         };
 
-        if selection < resolves.len() {
-            let resolve = &resolves[selection];
-            self.execute_resolve(resolve)?;
+        // 选择全部解析组时依次执行，共用测速配置的解析组只测速一次
+        let selected = if selection < resolves.len() {
+            vec![resolves[selection].clone()]
+        } else {
+            resolves
+        };
+
+        let modes = ["执行解析", "预览变更（不修改DNS）"];
+        match self
+            .ui
+            .show_menu("请选择执行方式（按ESC返回上级）", &modes, 0)?
+        {
+            Some(0) => {}
+            Some(_) => {
+                let reuse_result = selected.iter().all(|r| self.has_result_csv(r))
+                    && self.ui.confirm("复用已有的测速结果？", true)?;
+                self.mode = RunMode::Plan { reuse_result };
+            }
+            None => return Ok(()),
+        }
+
+        self.execute_resolves(&selected)?;
+
+        if self.mode.is_plan() {
+            self.ui.pause("按回车键继续...")?;
         }
 
         Ok(())
     }
 
+    /// 解析组的测速结果文件是否已存在
+    fn has_result_csv(&self, resolve: &Resolve) -> bool {
+        self.test_settings(resolve)
+            .map(|settings| Path::new(&self.tester.result_path(settings.cf_command)).exists())
+            .unwrap_or(false)
+    }
+
+    /// 解析组实际使用的测速参数和 IPv4、IPv6 地址来源，设置了共享测速配置时取自该配置
+    fn test_settings<'a>(&'a self, resolve: &'a Resolve) -> Result<TestSettings<'a>> {
+        if resolve.test_profile.is_empty() {
+            return Ok(TestSettings {
+                cf_command: &resolve.cf_command,
+                v4_sources: source_list(&resolve.v4_url, &resolve.v4_sources),
+                v6_sources: source_list(&resolve.v6_url, &resolve.v6_sources),
+                cidr_sample: resolve.cidr_sample,
+                builtin_sample: resolve.builtin_sample,
+            });
+        }
+        let profile = self
+            .config
+            .test_profile
+            .iter()
+            .flatten()
+            .find(|p| p.profile_name == resolve.test_profile)
+            .ok_or_else(|| anyhow::anyhow!("未找到指定的测速配置: {}", resolve.test_profile))?;
+        Ok(TestSettings {
+            cf_command: &profile.cf_command,
+            v4_sources: source_list(&profile.v4_url, &profile.v4_sources),
+            v6_sources: source_list(&profile.v6_url, &profile.v6_sources),
+            cidr_sample: profile.cidr_sample,
+            builtin_sample: profile.builtin_sample,
+        })
+    }
+
+    /// 配置文件中自定义的 Cloudflare IP 段
+    pub(super) fn custom_ranges(&self, ipv4: bool) -> &[String] {
+        match &self.config.cloudflare_ranges {
+            Some(ranges) if ipv4 => &ranges.v4,
+            Some(ranges) => &ranges.v6,
+            None => &[],
+        }
+    }
+
     fn get_resolves(&self) -> Vec<Resolve> {
         self.config.resolve.clone().unwrap_or_default()
     }
 
-    fn execute_resolve_by_name(&mut self, ddns_name: &str) -> Result<()> {
-        let resolve = self
-            .config
+    fn find_resolve(&self, ddns_name: &str) -> Result<Resolve> {
+        self.config
             .resolve
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("未找到任何解析组"))?
             .iter()
             .find(|r| r.ddns_name == ddns_name)
-            .ok_or_else(|| anyhow::anyhow!("未找到指定的解析组: {}", ddns_name))?;
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("未找到指定的解析组: {}", ddns_name))
+    }
 
-        self.execute_resolve(resolve)?;
+    /// 依次执行多个解析组，某个解析组失败时继续执行其余解析组
+    fn execute_resolves(&mut self, resolves: &[Resolve]) -> Result<()> {
+        // 共用测速配置的解析组按其中最大的IP数量只测速一次
+        self.test_results.borrow_mut().clear();
+        self.test_counts.clear();
+        for resolve in resolves.iter().filter(|r| !r.test_profile.is_empty()) {
+            let counts = self
+                .test_counts
+                .entry(resolve.test_profile.clone())
+                .or_default();
+            counts.0 = counts.0.max(resolve.v4_num);
+            counts.1 = counts.1.max(resolve.v6_num);
+        }
+
+        if resolves.len() == 1 {
+            return self.execute_resolve(&resolves[0]);
+        }
+
+        let mut failed = 0;
+        for resolve in resolves {
+            crate::print_section_header(&format!("解析组 {}", resolve.ddns_name));
+            if let Err(e) = self.execute_resolve(resolve) {
+                error_println(format_args!("解析组 {} 执行失败: {}", resolve.ddns_name, e));
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(anyhow::anyhow!("{} 个解析组执行失败", failed));
+        }
         Ok(())
     }
 
     fn execute_resolve(&self, resolve: &Resolve) -> Result<()> {
-        // 获取账户信息
-        let (x_email, zone_id, api_key) = if resolve.add_ddns != "未指定" {
-            let account = self
-                .config
-                .account
-                .iter()
-                .find(|a| a.account_name == resolve.add_ddns)
-                .ok_or_else(|| anyhow::anyhow!("未找到指定的账户: {}", resolve.add_ddns))?;
-            // 将 &String 转换为 &str，与 else 分支的类型匹配
-            (
-                account.x_email.as_str(),
-                account.zone_id.as_str(),
-                account.api_key.as_str(),
-            )
-        } else {
-            // 使用静态空字符串，避免悬垂引用
-            ("", "", "")
-        };
+        self.request_budget.reset();
+
+        // 主目标与附加目标共用同一次测速结果，未指定账户组时只测速和推送
+        let mut targets = Vec::new();
+        if resolve.add_ddns != "未指定" {
+            targets.push(DdnsTarget::new(
+                self.find_account(&resolve.add_ddns)?,
+                &resolve.hostname1,
+                &resolve.hostname2,
+            )?);
+        }
+        for target in &resolve.targets {
+            targets.push(DdnsTarget::new(
+                self.find_account(&target.add_ddns)?,
+                &target.hostname1,
+                &target.hostname2,
+            )?);
+        }
 
         // 获取插件配置
         #[cfg(target_os = "linux")]
@@ -139,21 +302,20 @@ impl Start {
             .map(|p| p.clien.as_str())
             .unwrap_or(&default_clien);
 
+        let test_settings = self.test_settings(resolve)?;
+        let test_profile = Some(resolve.test_profile.as_str()).filter(|p| !p.is_empty());
+
         // 直接执行DDNS更新逻辑
         self.run_start_ddns(
-            &resolve.add_ddns,
             &resolve.ddns_name,
-            x_email,
-            zone_id,
-            api_key,
-            &resolve.hostname1,
-            &resolve.hostname2,
+            &targets,
             resolve.v4_num,
             resolve.v6_num,
-            &resolve.cf_command,
-            &resolve.v4_url,
-            &resolve.v6_url,
+            &test_settings,
+            test_profile,
             &resolve.push_mod,
+            &RecordSettings::from(resolve),
+            &resolve.distribution,
             #[cfg(target_os = "linux")]
             clien,
         )?;
@@ -161,22 +323,26 @@ impl Start {
         Ok(())
     }
 
+    /// 按名称查找账户
+    fn find_account(&self, account_name: &str) -> Result<&Account> {
+        self.config
+            .account
+            .iter()
+            .find(|a| a.account_name == account_name)
+            .ok_or_else(|| anyhow::anyhow!("未找到指定的账户: {}", account_name))
+    }
+
     /// 执行消息推送的统一封装
     pub fn execute_push(&self, params: PushParams) -> Result<()> {
         if !params.push_mod.is_empty() && params.push_mod != "不设置" {
-            // 使用 create_domain_ip_mapping 创建域名和IP的映射关系
-            let domain_ip_mapping =
-                create_domain_ip_mapping(params.ips, params.domains, params.add_ddns);
-
             let run_push_params = RunPushParams {
                 push_mod: params.push_mod,
-                hostnames: params.hostnames,
                 v4_num: params.v4_num,
                 v6_num: params.v6_num,
                 ip_type: params.ip_type,
-                csvfile: &get_result_csv_path(params.csvfile),
+                csvfile: &self.tester.result_path(params.csvfile),
                 ddns_name: params.ddns_name,
-                domain_ip_mapping: &domain_ip_mapping,
+                domain_ip_mapping: params.domain_ip_mapping,
             };
 
             self.run_push(run_push_params)?;
@@ -184,10 +350,14 @@ impl Start {
         Ok(())
     }
 
+    /// 通过已配置的推送方式发送一条通知
+    pub fn push_notice(&self, push_mod: &str, message: &str) -> Result<()> {
+        self.push_service.push_notice(push_mod, message)
+    }
+
     fn run_push(&self, params: RunPushParams) -> Result<()> {
         self.push_service.run_push(
             params.push_mod,
-            params.hostnames,
             params.v4_num,
             params.v6_num,
             params.ip_type,
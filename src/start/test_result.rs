@@ -0,0 +1,261 @@
+use super::ip_source::{ParsedLine, SourceEntry, parse_line};
+use crate::ResultColumns;
+use anyhow::{Context, Result};
+use std::net::IpAddr;
+use std::path::Path;
+
+// 各列可能的标题，CloudflareST-Rust 和 XIU2 CloudflareSpeedTest 的结果都能识别
+const IP_HEADERS: &[&str] = &["ip地址", "ip", "ipaddress"];
+const LOSS_HEADERS: &[&str] = &["丢包率", "loss", "lossrate"];
+const LATENCY_HEADERS: &[&str] = &["平均延迟", "延迟", "latency", "averagedelay"];
+const SPEED_HEADERS: &[&str] = &["下载速度(mb/s)", "下载速度", "speed", "downloadspeed(mb/s)"];
+const COLO_HEADERS: &[&str] = &["地区码", "数据中心", "colo", "datacenter"];
+
+/// 测速结果文件中的一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedTestResult {
+    pub ip: IpAddr,
+    /// 丢包率，0 到 1
+    pub loss_rate: Option<f64>,
+    /// 平均延迟，单位毫秒
+    pub latency: f64,
+    /// 下载速度，单位 MB/s，未测下载速度时为空
+    pub speed: Option<f64>,
+    pub colo: Option<String>,
+}
+
+/// 按标题确定的各列位置
+struct Columns {
+    ip: usize,
+    loss_rate: Option<usize>,
+    latency: usize,
+    speed: Option<usize>,
+    colo: Option<usize>,
+}
+
+impl Columns {
+    /// 按标题确定各列，custom 中填写了列名的列只按该列名查找
+    fn from_headers(headers: &csv::StringRecord, custom: &ResultColumns) -> Result<Self> {
+        let find = |custom: &str, names: &[&str]| {
+            let custom = normalize_header(custom);
+            headers.iter().position(|header| {
+                let header = normalize_header(header);
+                if custom.is_empty() {
+                    names.contains(&header.as_str())
+                } else {
+                    header == custom
+                }
+            })
+        };
+        let required = |custom: &str, names: &[&str], column: &str| {
+            find(custom, names).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "测速结果缺少「{}」列，标题行为: {}",
+                    if custom.is_empty() { column } else { custom },
+                    headers.iter().collect::<Vec<_>>().join(",")
+                )
+            })
+        };
+
+        Ok(Self {
+            ip: required(&custom.ip, IP_HEADERS, "IP 地址")?,
+            loss_rate: find(&custom.loss_rate, LOSS_HEADERS),
+            latency: required(&custom.latency, LATENCY_HEADERS, "平均延迟")?,
+            speed: find(&custom.speed, SPEED_HEADERS),
+            colo: find(&custom.colo, COLO_HEADERS),
+        })
+    }
+
+    fn parse(&self, record: &csv::StringRecord) -> Result<SpeedTestResult> {
+        let field = |index: usize| record.get(index).unwrap_or_default();
+        let optional = |index: Option<usize>| index.map(field).filter(|value| !value.is_empty());
+        let number = |value: &str, column: &str| {
+            value
+                .trim_end_matches('%')
+                .parse::<f64>()
+                .map_err(|_| anyhow::anyhow!("{}「{}」不是数字", column, value))
+        };
+
+        let ip = match parse_line(field(self.ip)) {
+            ParsedLine::Entry(SourceEntry::Ip(ip)) => ip,
+            _ => return Err(anyhow::anyhow!("IP 地址「{}」无效", field(self.ip))),
+        };
+        let loss_rate = optional(self.loss_rate)
+            .map(|value| {
+                number(value, "丢包率").map(|rate| {
+                    if value.ends_with('%') {
+                        rate / 100.0
+                    } else {
+                        rate
+                    }
+                })
+            })
+            .transpose()?;
+        let latency = number(
+            field(self.latency).trim_end_matches("ms").trim(),
+            "平均延迟",
+        )?;
+        let speed = optional(self.speed)
+            .map(|value| number(value, "下载速度"))
+            .transpose()?;
+
+        Ok(SpeedTestResult {
+            ip,
+            loss_rate,
+            latency,
+            speed,
+            colo: optional(self.colo).map(ToString::to_string),
+        })
+    }
+}
+
+/// 解析测速结果内容，按标题行确定各列，格式不符时返回说明原因的错误
+pub fn parse_results(content: &str, custom: &ResultColumns) -> Result<Vec<SpeedTestResult>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let columns = Columns::from_headers(reader.headers()?, custom)?;
+
+    let mut results = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        // 行号从标题行之后的第 2 行开始
+        let result = columns
+            .parse(&record)
+            .with_context(|| format!("测速结果第 {} 行格式不正确", index + 2))?;
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// 读取测速结果文件，文件不存在时返回空列表
+pub fn read_results(path: &str, custom: &ResultColumns) -> Result<Vec<SpeedTestResult>> {
+    if !Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    parse_results(&content, custom).with_context(|| format!("无法解析测速结果文件 {}", path))
+}
+
+/// 比较标题时忽略空白和大小写
+fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// 按IP类型筛选测速结果，ip_type 为空时不筛选，num 为 0 时不限数量
+pub fn filter_results<'a>(
+    results: &'a [SpeedTestResult],
+    ip_type: &str,
+    num: u32,
+) -> Vec<&'a SpeedTestResult> {
+    results
+        .iter()
+        .filter(|result| match ip_type {
+            "IPv4" => result.ip.is_ipv4(),
+            "IPv6" => result.ip.is_ipv6(),
+            _ => true,
+        })
+        .take(if num == 0 { usize::MAX } else { num as usize })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Result<Vec<SpeedTestResult>> {
+        parse_results(content, &ResultColumns::default())
+    }
+
+    #[test]
+    fn parses_original_layout() {
+        let content = "\
+IP 地址,已发送,已接收,丢包率,平均延迟,下载速度(MB/s),地区码
+104.16.0.1,4,4,0.00,120.50,15.20,HKG
+2606:4700::1,4,3,0.25,180.00,8.00,LAX
+";
+        let results = parse(content).unwrap();
+        assert_eq!(
+            results[0],
+            SpeedTestResult {
+                ip: "104.16.0.1".parse().unwrap(),
+                loss_rate: Some(0.0),
+                latency: 120.5,
+                speed: Some(15.2),
+                colo: Some("HKG".to_string()),
+            }
+        );
+        assert_eq!(results[1].ip, "2606:4700::1".parse::<IpAddr>().unwrap());
+        assert_eq!(results[1].loss_rate, Some(0.25));
+    }
+
+    #[test]
+    fn finds_reordered_columns_by_header() {
+        let content = "\
+Colo,Speed,Latency,Loss,IP
+SJC,20.5,95 ms,10%,104.16.0.2
+";
+        let results = parse(content).unwrap();
+        assert_eq!(results[0].ip, "104.16.0.2".parse::<IpAddr>().unwrap());
+        assert_eq!(results[0].latency, 95.0);
+        assert_eq!(results[0].loss_rate, Some(0.1));
+        assert_eq!(results[0].speed, Some(20.5));
+        assert_eq!(results[0].colo.as_deref(), Some("SJC"));
+    }
+
+    #[test]
+    fn allows_missing_optional_columns() {
+        let content = "IP 地址,平均延迟\n104.16.0.3,88.00\n\n";
+        let results = parse(content).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].latency, 88.0);
+        assert_eq!(
+            (results[0].loss_rate, results[0].speed, &results[0].colo),
+            (None, None, &None)
+        );
+
+        // 未测下载速度时该列为空
+        let content = "IP 地址,平均延迟,下载速度(MB/s)\n104.16.0.3,88.00,\n";
+        assert_eq!(parse(content).unwrap()[0].speed, None);
+    }
+
+    #[test]
+    fn reports_malformed_row() {
+        let content = "IP 地址,平均延迟\n104.16.0.1,80\n104.16.0.2,fast\n";
+        let error = format!("{:#}", parse(content).unwrap_err());
+        assert!(error.contains("第 3 行"), "{}", error);
+        assert!(error.contains("fast"), "{}", error);
+
+        let content = "IP 地址,平均延迟\nnot-an-ip,80\n";
+        let error = format!("{:#}", parse(content).unwrap_err());
+        assert!(error.contains("第 2 行"), "{}", error);
+    }
+
+    #[test]
+    fn reports_missing_required_column() {
+        let error = parse("IP 地址,丢包率\n104.16.0.1,0\n").unwrap_err();
+        assert!(error.to_string().contains("平均延迟"), "{}", error);
+    }
+
+    #[test]
+    fn uses_custom_column_names() {
+        let columns = ResultColumns {
+            ip: "addr".to_string(),
+            latency: "rtt".to_string(),
+            ..Default::default()
+        };
+        let content = "addr,rtt,ip\n104.16.0.4,70,203.0.113.9\n";
+        let results = parse_results(content, &columns).unwrap();
+        assert_eq!(results[0].ip, "104.16.0.4".parse::<IpAddr>().unwrap());
+        assert_eq!(results[0].latency, 70.0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,100 @@
+use super::test_result::{SpeedTestResult, read_results};
+use super::utils::parse_cf_command_for_file;
+use crate::{CLOUDFLARE_SPEED_TEST, CLOUDFLAREST_RUST, ResultColumns, TesterConfig, TesterKind};
+use anyhow::Result;
+use std::process::Command;
+
+/// 本次运行使用的测速程序：可执行文件、参数名和结果列名
+#[derive(Debug, Clone)]
+pub struct Tester {
+    pub name: String,
+    pub path: String,
+    pub download_count_flag: String,
+    pub print_count_flag: String,
+    pub input_file_flag: String,
+    pub output_file_flag: String,
+    pub default_output: String,
+    pub columns: ResultColumns,
+}
+
+impl Tester {
+    /// 按测速程序类型取默认设置，再用配置中填写的项覆盖
+    pub fn from_config(config: Option<&TesterConfig>) -> Self {
+        let default_config = TesterConfig::default();
+        let config = config.unwrap_or(&default_config);
+
+        // CloudflareST-Rust 与 XIU2 CloudflareSpeedTest 的参数名相同
+        let name = match config.kind {
+            TesterKind::CloudflareStRust => CLOUDFLAREST_RUST,
+            TesterKind::CloudflareSpeedTest => CLOUDFLARE_SPEED_TEST,
+            TesterKind::Custom => "",
+        };
+        let path = if !config.path.is_empty() || name.is_empty() {
+            config.path.clone()
+        } else if cfg!(target_os = "windows") {
+            format!(".\\{}", name)
+        } else {
+            format!("./{}", name)
+        };
+        let flag = |value: &Option<String>, default: &str| {
+            value.clone().unwrap_or_else(|| default.to_string())
+        };
+        let flags = &config.flags;
+
+        Self {
+            name: if name.is_empty() {
+                config.path.clone()
+            } else {
+                name.to_string()
+            },
+            path,
+            download_count_flag: flag(&flags.download_count, "-dn"),
+            print_count_flag: flag(&flags.print_count, "-p"),
+            input_file_flag: flag(&flags.input_file, "-f"),
+            output_file_flag: flag(&flags.output_file, "-o"),
+            default_output: flag(&flags.default_output, "result.csv"),
+            columns: config.columns.clone(),
+        }
+    }
+
+    /// 测速参数中读取IP地址的文件
+    pub fn input_file(&self, cf_command: &str) -> Option<String> {
+        if self.input_file_flag.is_empty() {
+            return None;
+        }
+        parse_cf_command_for_file(cf_command, &self.input_file_flag)
+    }
+
+    /// 测速结果文件路径，测速参数中指定了结果文件时优先使用
+    pub fn result_path(&self, cf_command: &str) -> String {
+        Some(&self.output_file_flag)
+            .filter(|flag| !flag.is_empty())
+            .and_then(|flag| parse_cf_command_for_file(cf_command, flag))
+            .unwrap_or_else(|| self.default_output.clone())
+    }
+
+    /// 构造测速命令，test_num 大于 0 时按支持的参数限制下载测速和输出的数量
+    pub fn command(&self, cf_command: &str, test_num: u32) -> Result<Command> {
+        if self.path.is_empty() {
+            return Err(anyhow::anyhow!("未设置测速程序的路径"));
+        }
+
+        let mut cmd = Command::new(&self.path);
+        cmd.args(cf_command.split_whitespace());
+
+        if test_num > 0 {
+            let num_str = test_num.to_string();
+            for flag in [&self.download_count_flag, &self.print_count_flag] {
+                if !flag.is_empty() {
+                    cmd.arg(flag).arg(&num_str);
+                }
+            }
+        }
+        Ok(cmd)
+    }
+
+    /// 读取测速结果，按该测速程序的列名解析
+    pub fn read_results(&self, cf_command: &str) -> Result<Vec<SpeedTestResult>> {
+        read_results(&self.result_path(cf_command), &self.columns)
+    }
+}
\ No newline at end of file
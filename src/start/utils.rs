@@ -1,5 +1,8 @@
 // 工具函数模块
 
+use super::ddns_operations::DdnsTarget;
+use crate::Distribution;
+
 /// 解析 cf_command 获取指定参数(-f或-o)指定的文件路径
 pub fn parse_cf_command_for_file(cf_command: &str, param: &str) -> Option<String> {
     let mut parts = cf_command.split_whitespace();
@@ -11,32 +14,94 @@ pub fn parse_cf_command_for_file(cf_command: &str, param: &str) -> Option<String
     None
 }
 
-/// 解析 cf_command 获取 -o 参数指定的文件路径
-pub fn parse_cf_command_for_output_file(cf_command: &str) -> Option<String> {
-    parse_cf_command_for_file(cf_command, "-o")
+/// 由二级域名和一级域名拼出完整域名，"@" 为一级域名本身，"*" 和 "*.sub" 为泛解析
+pub fn full_domain(sub: &str, hostname1: &str) -> String {
+    if sub == "@" {
+        hostname1.to_string()
+    } else {
+        format!("{}.{}", sub, hostname1)
+    }
 }
 
-/// 获取result.csv文件路径，优先使用-o参数指定的文件
-pub fn get_result_csv_path(cf_command: &str) -> String {
-    parse_cf_command_for_output_file(cf_command).unwrap_or_else(|| "result.csv".to_string())
+/// 为每个解析目标分别创建域名和IP的映射关系并依次合并
+/// 没有解析目标（未指定账户组）时，域名处设为空字符串
+pub fn create_targets_ip_mapping(
+    ips: &[String],
+    targets: &[DdnsTarget],
+    distribution: &Distribution,
+) -> Vec<(String, String)> {
+    if targets.is_empty() {
+        return create_domain_ip_mapping(ips, &[], distribution);
+    }
+    targets
+        .iter()
+        .flat_map(|target| create_domain_ip_mapping(ips, &target.domains, distribution))
+        .collect()
 }
 
 /// 创建域名和IP的映射关系，格式为 [[域名, IP], [域名, IP], ...]
-/// 如果域名为空（未指定），则域名处设为极狐空字符串
+/// 如果没有域名，则域名处设为空字符串
 pub fn create_domain_ip_mapping(
     ips: &[String],
     domains: &[String],
-    add_ddns: &str,
+    distribution: &Distribution,
 ) -> Vec<(String, String)> {
-    ips.iter()
-        .enumerate()
-        .map(|(i, ip)| {
-            if add_ddns == "未指定" || domains.is_empty() {
-                (String::new(), ip.to_string())
-            } else {
-                let domain_index = i % domains.len();
-                (domains[domain_index].to_string(), ip.to_string())
-            }
+    if domains.is_empty() {
+        return ips
+            .iter()
+            .map(|ip| (String::new(), ip.to_string()))
+            .collect();
+    }
+
+    let pair = |domain: &String, ip: &String| (domain.to_string(), ip.to_string());
+    match distribution {
+        Distribution::RoundRobin => ips
+            .iter()
+            .enumerate()
+            .map(|(i, ip)| pair(&domains[i % domains.len()], ip))
+            .collect(),
+        Distribution::All => domains
+            .iter()
+            .flat_map(|domain| ips.iter().map(move |ip| pair(domain, ip)))
+            .collect(),
+        Distribution::Best { count } => domains
+            .iter()
+            .flat_map(|domain| {
+                ips.iter()
+                    .take(*count as usize)
+                    .map(move |ip| pair(domain, ip))
+            })
+            .collect(),
+        Distribution::Chunks => {
+            // 无法均分时，靠前的域名多分一个
+            let base = ips.len() / domains.len();
+            let extra = ips.len() % domains.len();
+            let counts: Vec<usize> = (0..domains.len())
+                .map(|i| base + usize::from(i < extra))
+                .collect();
+            assign_in_order(ips, domains, &counts)
+        }
+        Distribution::Counts { counts } => {
+            // 未指定数量的域名不分配IP
+            let counts: Vec<usize> = (0..domains.len())
+                .map(|i| counts.get(i).copied().unwrap_or(0) as usize)
+                .collect();
+            assign_in_order(ips, domains, &counts)
+        }
+    }
+}
+
+/// 按顺序依次为每个域名取出指定数量的IP，IP不足时靠后的域名分得更少
+fn assign_in_order(ips: &[String], domains: &[String], counts: &[usize]) -> Vec<(String, String)> {
+    let mut ips = ips.iter();
+    domains
+        .iter()
+        .zip(counts)
+        .flat_map(|(domain, &count)| {
+            ips.by_ref()
+                .take(count)
+                .map(|ip| (domain.to_string(), ip.to_string()))
+                .collect::<Vec<_>>()
         })
         .collect()
 }
\ No newline at end of file
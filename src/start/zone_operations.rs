@@ -0,0 +1,149 @@
+use super::dns_operations::{DnsProvider, DnsRecord, Zone, display_name, match_zone};
+use crate::Account;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+/// 一个区域的 A/AAAA 记录缓存
+#[derive(Debug, Default)]
+pub struct ZoneRecords {
+    records: Vec<DnsRecord>,
+    /// 本次运行中已被改动、下次使用前需要重新读取的记录类型
+    stale: HashSet<String>,
+}
+
+/// 要读取的记录类型，未指定时为 A 与 AAAA
+fn record_types(record_type: Option<&str>) -> Vec<&str> {
+    record_type.map_or(vec!["A", "AAAA"], |rt| vec![rt])
+}
+
+/// 缓存键，不同服务商的区域ID可能相同
+fn cache_key(provider: &dyn DnsProvider, zone_id: &str) -> String {
+    format!("{}/{}", provider.name(), zone_id)
+}
+
+/// 区域的显示名称，从已缓存的区域列表中查找，未找到时使用区域ID
+fn zone_name(zones: &HashMap<String, Vec<Zone>>, zone_id: &str) -> String {
+    let name = zones
+        .values()
+        .flatten()
+        .find(|zone| zone.id == zone_id)
+        .map_or(zone_id, |zone| zone.name.as_str());
+    display_name(name)
+}
+
+pub trait ZoneOperations {
+    /// 获取域名所属的区域ID，账户已指定区域ID时直接使用，否则根据域名自动识别
+    fn get_zone_id(
+        &self,
+        account: &Account,
+        provider: &dyn DnsProvider,
+        domain: &str,
+    ) -> Result<String>;
+
+    /// 获取区域内指定类型的全部记录，整个区域在本次运行内只读取一次，IPv4 与 IPv6 共用
+    fn zone_records(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_id: &str,
+        record_type: Option<&str>,
+    ) -> Result<Vec<DnsRecord>>;
+
+    /// 区域内指定类型的记录已被改动，下次使用时重新读取
+    fn invalidate_zone_records(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_id: &str,
+        record_type: Option<&str>,
+    );
+}
+
+impl ZoneOperations for super::start_struct::Start {
+    fn get_zone_id(
+        &self,
+        account: &Account,
+        provider: &dyn DnsProvider,
+        domain: &str,
+    ) -> Result<String> {
+        if !account.zone_id.trim().is_empty() {
+            return Ok(account.zone_id.trim().to_string());
+        }
+
+        // 同一账户的区域列表在本次运行内只获取一次
+        let mut cache = self.zone_cache.borrow_mut();
+        if !cache.contains_key(&account.account_name) {
+            let zones = provider.list_zones()?;
+            crate::info_println(format_args!(
+                "账户 {} 下共有 {} 个区域",
+                account.account_name,
+                zones.len()
+            ));
+            cache.insert(account.account_name.clone(), zones);
+        }
+
+        match_zone(&cache[&account.account_name], domain)
+            .map(|zone| zone.id.clone())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "账户 {} 下未找到域名 {} 所属的区域",
+                    account.account_name,
+                    display_name(domain)
+                )
+            })
+    }
+
+    fn zone_records(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_id: &str,
+        record_type: Option<&str>,
+    ) -> Result<Vec<DnsRecord>> {
+        let key = cache_key(provider, zone_id);
+        let types = record_types(record_type);
+        let mut cache = self.record_cache.borrow_mut();
+
+        let fresh = cache
+            .get(&key)
+            .is_some_and(|zone| types.iter().all(|t| !zone.stale.contains(*t)));
+        if !fresh {
+            let records = provider.list_zone_records(zone_id)?;
+            crate::info_println(format_args!(
+                "区域 {} 共有 {} 条 A/AAAA 记录",
+                zone_name(&self.zone_cache.borrow(), zone_id),
+                records.len()
+            ));
+            cache.insert(
+                key.clone(),
+                ZoneRecords {
+                    records,
+                    stale: HashSet::new(),
+                },
+            );
+        }
+
+        Ok(cache[&key]
+            .records
+            .iter()
+            .filter(|r| types.contains(&r.record_type.as_str()))
+            .cloned()
+            .collect())
+    }
+
+    fn invalidate_zone_records(
+        &self,
+        provider: &dyn DnsProvider,
+        zone_id: &str,
+        record_type: Option<&str>,
+    ) {
+        if let Some(zone) = self
+            .record_cache
+            .borrow_mut()
+            .get_mut(&cache_key(provider, zone_id))
+        {
+            zone.stale.extend(
+                record_types(record_type)
+                    .into_iter()
+                    .map(ToString::to_string),
+            );
+        }
+    }
+}
\ No newline at end of file